@@ -0,0 +1,50 @@
+//! Throughput of `ErasureCoder::encode` for each backend.
+//!
+//! Run by `cargo bench` (add `--features isa-l` to include ISA-L).
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use liberasurecode::{Backend, Builder};
+use std::num::NonZeroUsize;
+
+const DATA_SIZES: &[usize] = &[64 * 1024, 1024 * 1024, 4 * 1024 * 1024];
+const PARAMS: &[(usize, usize)] = &[(4, 2), (10, 4)];
+
+fn backends() -> Vec<Backend> {
+    vec![
+        Backend::PureRustRs,
+        #[cfg(feature = "jerasure")]
+        Backend::JerasureRsVand,
+        #[cfg(feature = "jerasure")]
+        Backend::JerasureRsCauchy,
+        #[cfg(feature = "isa-l")]
+        Backend::IsaLRsVand,
+    ]
+}
+
+fn encode(c: &mut Criterion) {
+    for &(k, m) in PARAMS {
+        let mut group = c.benchmark_group(format!("encode/{}+{}", k, m));
+        for &size in DATA_SIZES {
+            let data = (0..size).map(|i| (i * 31) as u8).collect::<Vec<_>>();
+            group.throughput(Throughput::Bytes(size as u64));
+            for backend in backends() {
+                if !backend.is_available() {
+                    continue;
+                }
+                let coder =
+                    Builder::new(NonZeroUsize::new(k).unwrap(), NonZeroUsize::new(m).unwrap())
+                        .backend(backend)
+                        .finish()
+                        .unwrap();
+                group.bench_with_input(
+                    BenchmarkId::new(format!("{:?}", backend), size),
+                    &data,
+                    |b, data| b.iter(|| coder.encode(data).unwrap()),
+                );
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
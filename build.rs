@@ -1,11 +1,128 @@
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
+/// The name of the pkg-config package installed by liberasurecode.
+const PKG_CONFIG_NAME: &str = "erasurecode-1";
+
+/// The versions of liberasurecode whose API and fragment format are compatible with this crate.
+const SUPPORTED_VERSIONS: std::ops::Range<&str> = "1.5.0".."2.0.0";
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=LIBERASURECODE_SYSTEM");
+    println!("cargo:rustc-check-cfg=cfg(liberasurecode_system)");
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+
+    if !cfg!(feature = "native") {
+        // Only `Backend::PureRustRs` is available, which needs no native libraries.
+        return;
+    }
+    if env::var_os("DOCS_RS").is_some() {
+        // docs.rs has no network access,
+        // and documenting the crate does not need the native libraries to be linked.
+        return;
+    }
+    if env::var_os("LIBERASURECODE_SYSTEM").is_some_and(|v| v == "1") {
+        link_system_library();
+    } else if cfg!(feature = "vendored") {
+        build_vendored_library();
+    } else {
+        build_bundled_library();
+    }
+}
+
+/// The static libraries built from the sources, in the order to be linked.
+///
+/// Each item is a pair of the library name and whether the library is enabled by the cargo features.
+const STATIC_LIBRARIES: &[(&str, bool)] = &[
+    ("erasurecode", true),
+    ("gf_complete", cfg!(feature = "jerasure")),
+    ("Jerasure", cfg!(feature = "jerasure")),
+    ("Xorcode", cfg!(feature = "flat-xor")),
+    ("isal", cfg!(feature = "isa-l")),
+    ("nullcode", true),
+    ("erasurecode_rs_vand", true),
+    ("erasurecode_static", true),
+];
+
+fn link_static_libraries(lib_dir: &Path) {
+    println!("cargo:rustc-link-search={}", lib_dir.display());
+    for (lib, enabled) in STATIC_LIBRARIES {
+        if *enabled {
+            println!("cargo:rustc-link-lib=static={}", lib);
+        }
+    }
+}
+
+/// The macros enabling the static backends in `static_backends.c` (see also `install_deps.sh`).
+#[cfg(feature = "vendored")]
+fn static_backend_macros() -> Vec<&'static str> {
+    let mut macros = vec![
+        "STATIC_BACKEND_NULLCODE",
+        "STATIC_BACKEND_ERASURECODE_RS_VAND",
+    ];
+    if cfg!(feature = "jerasure") {
+        macros.push("STATIC_BACKEND_JERASURE");
+    }
+    if cfg!(feature = "flat-xor") {
+        macros.push("STATIC_BACKEND_XORCODE");
+    }
+    if cfg!(feature = "isa-l") {
+        macros.push("STATIC_BACKEND_ISAL");
+    }
+    macros
+}
 
+/// Returns `"true"` if the given feature is enabled, otherwise `"false"` (for `install_deps.sh`).
+fn feature_flag(enabled: bool) -> &'static str {
+    if enabled {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// Links liberasurecode installed in the system.
+///
+/// liberasurecode loads its backends (e.g., Jerasure) by `dlopen(3)` at runtime,
+/// so only liberasurecode itself is linked. Whether it is linked statically or dynamically
+/// follows the environment variables of the `pkg-config` crate (e.g., `ERASURECODE_1_STATIC`).
+fn link_system_library() {
+    println!("cargo:rustc-cfg=liberasurecode_system");
+
+    let library = match pkg_config::Config::new()
+        .range_version(SUPPORTED_VERSIONS)
+        .probe(PKG_CONFIG_NAME)
+    {
+        Ok(library) => library,
+        Err(e) => panic!(
+            "LIBERASURECODE_SYSTEM=1 requires liberasurecode (>= {}, < {}) registered to pkg-config as `{}`: {}",
+            SUPPORTED_VERSIONS.start, SUPPORTED_VERSIONS.end, PKG_CONFIG_NAME, e
+        ),
+    };
+
+    // liberasurecode loads the backend libraries by `dlopen`, which reuses the libraries already
+    // loaded in the process, so those resolved by pkg-config are linked to make sure that
+    // the compatible builds (rather than the first ones found in the library path) are used.
+    let backend_libraries: &[&str] = if cfg!(feature = "jerasure") {
+        &["jerasure", "gf_complete"]
+    } else {
+        &[]
+    };
+    for name in backend_libraries {
+        if let Err(e) = pkg_config::Config::new().probe(name) {
+            panic!(
+                "LIBERASURECODE_SYSTEM=1 with the `jerasure` feature requires `{}` registered to pkg-config (liberasurecode {}): {}",
+                name, library.version, e
+            );
+        }
+    }
+}
+
+/// Builds liberasurecode and its dependencies from the sources, and links them statically.
+fn build_bundled_library() {
     let outdir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
     let build_dir = outdir.join("build");
     let _ = fs::remove_dir_all(&build_dir);
@@ -15,12 +132,17 @@ fn main() {
         "install_deps.sh",
         "liberasurecode.patch",
         "for_darwin_to_detect_compiler_flag.patch",
+        "static_backends.h",
+        "static_backends.c",
     ] {
         fs::copy(file, build_dir.join(file)).unwrap();
     }
 
     match Command::new("./install_deps.sh")
         .current_dir(&build_dir)
+        .env("WITH_JERASURE", feature_flag(cfg!(feature = "jerasure")))
+        .env("WITH_FLAT_XOR", feature_flag(cfg!(feature = "flat-xor")))
+        .env("WITH_ISA_L", feature_flag(cfg!(feature = "isa-l")))
         .stderr(Stdio::inherit())
         .output()
     {
@@ -37,5 +159,183 @@ fn main() {
         }
     }
 
-    println!("cargo:rustc-link-search={}/lib", build_dir.display());
+    link_static_libraries(&build_dir.join("lib"));
+}
+
+/// The sources of each library relative to the `src` directory of the vendored project.
+///
+/// The libraries disabled by the cargo features (see `STATIC_LIBRARIES`) are skipped.
+#[cfg(feature = "vendored")]
+const VENDORED_SOURCES: &[(&str, &str, &[&str])] = &[
+    (
+        "gf_complete",
+        "gf-complete",
+        &[
+            "gf.c",
+            "gf_method.c",
+            "gf_wgen.c",
+            "gf_w4.c",
+            "gf_w8.c",
+            "gf_w16.c",
+            "gf_w32.c",
+            "gf_w64.c",
+            "gf_w128.c",
+            "gf_rand.c",
+            "gf_general.c",
+        ],
+    ),
+    (
+        "Jerasure",
+        "jerasure",
+        &[
+            "galois.c",
+            "jerasure.c",
+            "reed_sol.c",
+            "cauchy.c",
+            "liberation.c",
+        ],
+    ),
+    (
+        "erasurecode",
+        "liberasurecode",
+        &[
+            "erasurecode.c",
+            "erasurecode_helpers.c",
+            "erasurecode_preprocessing.c",
+            "erasurecode_postprocessing.c",
+            "utils/chksum/crc32.c",
+            "utils/chksum/alg_sig.c",
+            "backends/null/null.c",
+            "backends/xor/flat_xor_hd.c",
+            "backends/jerasure/jerasure_rs_vand.c",
+            "backends/jerasure/jerasure_rs_cauchy.c",
+            "backends/isa-l/isa_l_common.c",
+            "backends/isa-l/isa_l_rs_vand.c",
+            "backends/isa-l/isa_l_rs_cauchy.c",
+            "backends/rs_vand/liberasurecode_rs_vand.c",
+            // `builtin/rs_vand/rs_galois.c` is also listed here by `Makefile.am`, but it is
+            // compiled only into `erasurecode_rs_vand` to avoid duplicate symbols when linking
+            // both libraries statically.
+            "backends/shss/shss.c",
+            "backends/phazrfs/libphazr.c",
+        ],
+    ),
+    (
+        "Xorcode",
+        "liberasurecode",
+        &[
+            "builtin/xor_codes/xor_code.c",
+            "builtin/xor_codes/xor_hd_code.c",
+        ],
+    ),
+    (
+        // Only the portable implementations are built since the optimized ones require `nasm`.
+        "isal",
+        "isa-l",
+        &["erasure_code/ec_base.c", "erasure_code/ec_base_aliases.c"],
+    ),
+    (
+        "nullcode",
+        "liberasurecode",
+        &["builtin/null_code/null_code.c"],
+    ),
+    (
+        "erasurecode_rs_vand",
+        "liberasurecode",
+        &[
+            "builtin/rs_vand/liberasurecode_rs_vand.c",
+            "builtin/rs_vand/rs_galois.c",
+        ],
+    ),
+];
+
+/// Builds liberasurecode and its dependencies from the sources in `vendor/` by the `cc` crate.
+///
+/// The sources are imported (and patched) by `vendor.sh` in advance,
+/// so this requires neither network access nor autotools.
+#[cfg(feature = "vendored")]
+fn build_vendored_library() {
+    println!("cargo:rerun-if-changed=vendor");
+    println!("cargo:rerun-if-changed=static_backends.c");
+    println!("cargo:rerun-if-changed=static_backends.h");
+
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let vendor_dir = manifest_dir.join("vendor");
+    if !vendor_dir.join("static_symbols.h").exists() {
+        panic!("The vendored sources are not found in `vendor/`; run `./vendor.sh` to import them");
+    }
+    // `liberasurecode.patch` is applied only if the `jerasure` feature is enabled,
+    // so the patched files are kept in `vendor/liberasurecode-jerasure` by `vendor.sh`.
+    let patched_dir = vendor_dir.join("liberasurecode-jerasure");
+    let mut include_dirs = Vec::new();
+    if cfg!(feature = "jerasure") {
+        include_dirs.push(patched_dir.join("include/erasurecode"));
+    }
+    include_dirs.extend_from_slice(&[
+        vendor_dir.join("liberasurecode/include"),
+        vendor_dir.join("liberasurecode/include/erasurecode"),
+        vendor_dir.join("liberasurecode/include/xor_codes"),
+        vendor_dir.join("liberasurecode/include/rs_vand"),
+        vendor_dir.join("liberasurecode/include/isa_l"),
+        vendor_dir.join("liberasurecode/include/shss"),
+        vendor_dir.join("jerasure/include"),
+        vendor_dir.join("gf-complete/include"),
+        vendor_dir.clone(),
+    ]);
+
+    let new_build = || {
+        let mut build = cc::Build::new();
+        build
+            .define("HAVE_CONFIG_H", None)
+            .warnings(false)
+            .cargo_metadata(false);
+        build
+    };
+    for (lib, project, sources) in VENDORED_SOURCES {
+        let enabled = STATIC_LIBRARIES
+            .iter()
+            .any(|(name, enabled)| name == lib && *enabled);
+        if !enabled {
+            continue;
+        }
+
+        let mut build = new_build();
+        let project_dir = vendor_dir.join(project);
+        let mut source_dirs = vec![project_dir.join("src")];
+        if *project == "liberasurecode" {
+            // Redirects `dlopen(3)` to the statically linked backends (see `static_backends.h`).
+            build
+                .flag("-include")
+                .flag(manifest_dir.join("static_backends.h").to_str().unwrap());
+            if cfg!(feature = "jerasure") {
+                source_dirs.insert(0, patched_dir.join("src"));
+            }
+        }
+        build
+            .include(project_dir.join("include"))
+            .includes(&include_dirs)
+            .files(sources.iter().map(|source| {
+                source_dirs
+                    .iter()
+                    .map(|dir| dir.join(source))
+                    .find(|path| path.exists())
+                    .unwrap_or_else(|| panic!("`{}` is not found in `vendor/`", source))
+            }))
+            .compile(lib);
+    }
+    let mut build = new_build();
+    for name in static_backend_macros() {
+        build.define(name, None);
+    }
+    build
+        .include(&vendor_dir)
+        .file(manifest_dir.join("static_backends.c"))
+        .compile("erasurecode_static");
+
+    link_static_libraries(Path::new(&env::var_os("OUT_DIR").unwrap()));
+}
+
+#[cfg(not(feature = "vendored"))]
+fn build_vendored_library() {
+    unreachable!()
 }
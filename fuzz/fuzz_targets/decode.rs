@@ -0,0 +1,22 @@
+#![no_main]
+use liberasurecode::DecodeOptions;
+use liberasurecode_fuzz::Input;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: Input| {
+    let (coder, fragments) = match input.prepare() {
+        Some(x) => x,
+        None => return,
+    };
+
+    let decoded = coder.decode(&fragments);
+    if input.mutations.is_empty() {
+        assert_eq!(decoded.as_ref(), Ok(&input.data));
+    }
+
+    let mut options = DecodeOptions::new();
+    options.force_metadata_checks(true).verify_checksum(true);
+    let _ = coder.decode_with(&fragments, &options);
+    let _ = coder.decode_borrowed(&fragments).map(|data| data.to_vec());
+    let _ = coder.decode_excluding_corrupted(&fragments);
+});
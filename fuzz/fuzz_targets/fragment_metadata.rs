@@ -0,0 +1,27 @@
+#![no_main]
+use liberasurecode::ErasureCoder;
+use libfuzzer_sys::fuzz_target;
+use std::num::NonZeroUsize;
+use std::sync::OnceLock;
+
+fn coder() -> &'static ErasureCoder {
+    static CODER: OnceLock<ErasureCoder> = OnceLock::new();
+    CODER.get_or_init(|| {
+        let data_fragments = NonZeroUsize::new(4).expect("Never fails");
+        let parity_fragments = NonZeroUsize::new(2).expect("Never fails");
+        ErasureCoder::new(data_fragments, parity_fragments).expect("Cannot make a coder")
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let coder = coder();
+    let _ = coder.fragment_metadata(data);
+    let _ = coder.is_valid_fragment(data);
+    let _ = coder.verify_stripe(&[data]);
+
+    // Also treats the input as a stripe of equally sized fragments.
+    if data.len() >= 2 {
+        let (a, b) = data.split_at(data.len() / 2);
+        let _ = coder.verify_stripe(&[a, b]);
+    }
+});
@@ -0,0 +1,29 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use liberasurecode_fuzz::Input;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct ReconstructInput {
+    input: Input,
+    indices: Vec<u8>,
+}
+
+fuzz_target!(|input: ReconstructInput| {
+    let (coder, fragments) = match input.input.prepare() {
+        Some(x) => x,
+        None => return,
+    };
+
+    // Out of range indices are also tested.
+    let indices = input
+        .indices
+        .iter()
+        .map(|&i| usize::from(i) % (coder.fragments().get() + 1))
+        .collect::<Vec<_>>();
+    if let Some(&index) = indices.first() {
+        let _ = coder.reconstruct(index, fragments.iter());
+    }
+    let _ = coder.reconstruct_many(&indices, fragments.iter());
+    let _ = coder.fragments_needed(&indices, &[]);
+});
@@ -0,0 +1,167 @@
+//! Helpers shared by the fuzz targets.
+//!
+//! Each target encodes fuzzer chosen data with a fuzzer chosen coder, and then feeds
+//! the resulting fragments mutated by [`Mutation`]s to the functions under test.
+//! The targets only check that the functions never crash (i.e., errors are fine).
+//!
+//! [`Mutation`]: ./enum.Mutation.html
+use arbitrary::Arbitrary;
+use liberasurecode::{available_backends, Builder, Checksum, ErasureCoder, FragmentMetadata};
+use std::num::NonZeroUsize;
+
+/// The configuration of a coder.
+#[derive(Debug, Arbitrary)]
+pub struct Config {
+    data_fragments: u8,
+    parity_fragments: u8,
+    backend: u8,
+    checksum: u8,
+}
+impl Config {
+    /// Makes the coder with this configuration.
+    ///
+    /// `None` is returned if the configuration is not supported by the chosen backend.
+    pub fn coder(&self) -> Option<ErasureCoder> {
+        let data_fragments = NonZeroUsize::new(usize::from(self.data_fragments % 12) + 1)?;
+        let parity_fragments = NonZeroUsize::new(usize::from(self.parity_fragments % 6) + 1)?;
+        let backends = available_backends().collect::<Vec<_>>();
+        let backend = backends[usize::from(self.backend) % backends.len()];
+        let checksum = match self.checksum % 3 {
+            0 => Checksum::None,
+            1 => Checksum::Crc32,
+            _ => Checksum::Md5,
+        };
+        Builder::new(data_fragments, parity_fragments)
+            .backend(backend)
+            .checksum(checksum)
+            .finish()
+            .ok()
+    }
+}
+
+/// A mutation applied to a set of valid fragments.
+#[derive(Debug, Arbitrary)]
+pub enum Mutation {
+    /// Truncates a fragment.
+    Truncate { fragment: usize, len: usize },
+
+    /// Appends bytes to a fragment.
+    Extend { fragment: usize, bytes: Vec<u8> },
+
+    /// Duplicates a fragment.
+    Duplicate { fragment: usize },
+
+    /// Removes a fragment.
+    Remove { fragment: usize },
+
+    /// Swaps the positions of two fragments.
+    Swap { a: usize, b: usize },
+
+    /// Overwrites the index recorded in the header of a fragment.
+    SetIndex { fragment: usize, index: u32 },
+
+    /// Overwrites a 32-bit word in the header of a fragment.
+    SetHeaderWord {
+        fragment: usize,
+        offset: usize,
+        value: u32,
+    },
+
+    /// Flips a bit in the header of a fragment.
+    FlipHeaderBit { fragment: usize, bit: usize },
+
+    /// Flips a bit in the payload of a fragment.
+    FlipPayloadBit { fragment: usize, bit: usize },
+}
+impl Mutation {
+    /// Applies this mutation to the given fragments.
+    pub fn apply(&self, fragments: &mut Vec<Vec<u8>>) {
+        if fragments.is_empty() {
+            return;
+        }
+        let n = fragments.len();
+        let header_size = FragmentMetadata::HEADER_SIZE;
+        match *self {
+            Mutation::Truncate { fragment, len } => {
+                let fragment = &mut fragments[fragment % n];
+                let len = len % (fragment.len() + 1);
+                fragment.truncate(len);
+            }
+            Mutation::Extend {
+                fragment,
+                ref bytes,
+            } => {
+                fragments[fragment % n].extend_from_slice(bytes);
+            }
+            Mutation::Duplicate { fragment } => {
+                let fragment = fragments[fragment % n].clone();
+                fragments.push(fragment);
+            }
+            Mutation::Remove { fragment } => {
+                fragments.remove(fragment % n);
+            }
+            Mutation::Swap { a, b } => {
+                fragments.swap(a % n, b % n);
+            }
+            Mutation::SetIndex { fragment, index } => {
+                let fragment = &mut fragments[fragment % n];
+                if fragment.len() >= 4 {
+                    fragment[..4].copy_from_slice(&index.to_ne_bytes());
+                }
+            }
+            Mutation::SetHeaderWord {
+                fragment,
+                offset,
+                value,
+            } => {
+                let fragment = &mut fragments[fragment % n];
+                if fragment.len() >= header_size {
+                    let offset = offset % (header_size - 3);
+                    fragment[offset..][..4].copy_from_slice(&value.to_ne_bytes());
+                }
+            }
+            Mutation::FlipHeaderBit { fragment, bit } => {
+                let fragment = &mut fragments[fragment % n];
+                let bits = fragment.len().min(header_size) * 8;
+                if bits > 0 {
+                    let bit = bit % bits;
+                    fragment[bit / 8] ^= 1 << (bit % 8);
+                }
+            }
+            Mutation::FlipPayloadBit { fragment, bit } => {
+                let fragment = &mut fragments[fragment % n];
+                let bits = fragment.len().saturating_sub(header_size) * 8;
+                if bits > 0 {
+                    let bit = header_size * 8 + bit % bits;
+                    fragment[bit / 8] ^= 1 << (bit % 8);
+                }
+            }
+        }
+    }
+}
+
+/// An input of the fuzz targets.
+#[derive(Debug, Arbitrary)]
+pub struct Input {
+    /// The configuration of the coder.
+    pub config: Config,
+
+    /// The data to be encoded.
+    pub data: Vec<u8>,
+
+    /// The mutations applied to the encoded fragments.
+    pub mutations: Vec<Mutation>,
+}
+impl Input {
+    /// Makes the coder, encodes the data and applies the mutations to the resulting fragments.
+    ///
+    /// `None` is returned if the configuration is not supported.
+    pub fn prepare(&self) -> Option<(ErasureCoder, Vec<Vec<u8>>)> {
+        let coder = self.config.coder()?;
+        let mut fragments = coder.encode(&self.data).ok()?;
+        for mutation in &self.mutations {
+            mutation.apply(&mut fragments);
+        }
+        Some((coder, fragments))
+    }
+}
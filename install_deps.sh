@@ -4,6 +4,9 @@ set -eux
 
 BUILD_DIR=$PWD
 MAKE_FLAGS=""
+WITH_JERASURE=${WITH_JERASURE:-true}
+WITH_FLAT_XOR=${WITH_FLAT_XOR:-true}
+WITH_ISA_L=${WITH_ISA_L:-false}
 export CPATH="${BUILD_DIR}/include:${BUILD_DIR}/include/jerasure:${CPATH:-}"
 export LIBRARY_PATH="${BUILD_DIR}/lib:${LIBRARY_PATH:-}"
 
@@ -13,35 +16,54 @@ case "$(uname)" in
     "Darwin") MAKE_FLAGS="-j$(sysctl -n hw.ncpu)"
 esac
 
-#
-# gf-complete
-#
-git clone https://github.com/ceph/gf-complete.git
-cd gf-complete/
-git checkout a6862d1
-./autogen.sh
-if [[ $DEBUG = true ]]; then
-    ./configure --disable-shared --with-pic --prefix $BUILD_DIR CFLAGS="${CFLAGS:-} -O0 -g"
-else
-    ./configure --disable-shared --with-pic --prefix $BUILD_DIR
+if [[ $WITH_JERASURE = true ]]; then
+    #
+    # gf-complete
+    #
+    git clone https://github.com/ceph/gf-complete.git
+    cd gf-complete/
+    git checkout a6862d1
+    ./autogen.sh
+    if [[ $DEBUG = true ]]; then
+        ./configure --disable-shared --with-pic --prefix $BUILD_DIR CFLAGS="${CFLAGS:-} -O0 -g"
+    else
+        ./configure --disable-shared --with-pic --prefix $BUILD_DIR
+    fi
+    make $MAKE_FLAGS install
+    cd ../
+
+    #
+    # jerasure
+    #
+    git clone https://github.com/ceph/jerasure.git
+    cd jerasure/
+    git checkout de1739c
+    autoreconf --force --install
+    if [[ $DEBUG = true ]]; then
+        ./configure --disable-shared --enable-static --with-pic --prefix $BUILD_DIR CFLAGS="${CFLAGS:-} -O0 -g"
+    else
+        ./configure --disable-shared --enable-static --with-pic --prefix $BUILD_DIR
+    fi
+    make $MAKE_FLAGS install
+    cd ../
 fi
-make $MAKE_FLAGS install
-cd ../
 
-#
-# jerasure
-#
-git clone https://github.com/ceph/jerasure.git
-cd jerasure/
-git checkout de1739c
-autoreconf --force --install
-if [[ $DEBUG = true ]]; then
-    ./configure --disable-shared --enable-static --with-pic --prefix $BUILD_DIR CFLAGS="${CFLAGS:-} -O0 -g"
-else
-    ./configure --disable-shared --enable-static --with-pic --prefix $BUILD_DIR
+if [[ $WITH_ISA_L = true ]]; then
+    #
+    # isa-l (requires `nasm` on x86_64)
+    #
+    git clone https://github.com/intel/isa-l.git
+    cd isa-l/
+    git checkout v2.30.0
+    ./autogen.sh
+    if [[ $DEBUG = true ]]; then
+        ./configure --disable-shared --with-pic --prefix $BUILD_DIR CFLAGS="${CFLAGS:-} -O0 -g"
+    else
+        ./configure --disable-shared --with-pic --prefix $BUILD_DIR
+    fi
+    make $MAKE_FLAGS install
+    cd ../
 fi
-make $MAKE_FLAGS install
-cd ../
 
 #
 # liberasurecode
@@ -55,10 +77,77 @@ if [ "$(uname)" == "Darwin" ]; then
     patch -p1 < ../for_darwin_to_detect_compiler_flag.patch
 fi
 ./autogen.sh
+LIBERASURECODE_LIBS=""
+if [[ $WITH_JERASURE = true ]]; then
+    LIBERASURECODE_LIBS="-lJerasure"
+fi
 if [[ $DEBUG = true ]]; then
-    LIBS="-lJerasure" ./configure --disable-shared --with-pic --prefix $BUILD_DIR CFLAGS="${CFLAGS:-} -O0 -g"
+    LIBS="$LIBERASURECODE_LIBS" ./configure --disable-shared --with-pic --prefix $BUILD_DIR CFLAGS="${CFLAGS:-} -O0 -g"
 else
-    LIBS="-lJerasure" ./configure --disable-shared --with-pic --prefix $BUILD_DIR
+    LIBS="$LIBERASURECODE_LIBS" ./configure --disable-shared --with-pic --prefix $BUILD_DIR
+fi
+if [[ $WITH_JERASURE = true ]]; then
+    patch -p1 < ../liberasurecode.patch # Applies a patch for linking Jerasure statically
+fi
+
+# Redirects `dlopen(3)` to the statically linked backends (see `static_backends.h`).
+# The test programs are skipped since they cannot be linked without `static_backends.c`.
+STATIC_BACKENDS="nullcode erasurecode_rs_vand"
+BUILTIN_DIRS="src/builtin/null_code src/builtin/rs_vand"
+if [[ $WITH_FLAT_XOR = true ]]; then
+    STATIC_BACKENDS="Xorcode $STATIC_BACKENDS"
+    BUILTIN_DIRS="src/builtin/xor_codes $BUILTIN_DIRS"
+fi
+if [[ $WITH_ISA_L = true ]]; then
+    STATIC_BACKENDS="isal $STATIC_BACKENDS"
+fi
+for dir in $BUILTIN_DIRS src; do
+    CPPFLAGS="$(sed -n 's/^CPPFLAGS = //p' $dir/Makefile) -include ${BUILD_DIR}/static_backends.h"
+    make $MAKE_FLAGS -C $dir CPPFLAGS="$CPPFLAGS" install
+done
+cd ../
+
+#
+# static backends
+#
+case "$(uname)" in
+    "Darwin") SYMBOL_PREFIX="_";;
+    *) SYMBOL_PREFIX=""
+esac
+# The symbols of each backend are guarded by `STATIC_BACKEND_<LIBRARY NAME>` macro
+# so that `static_symbols.h` can be shared by builds with different backends.
+#
+# ISA-L exports a lot of architecture specific functions, so only the ones looked up by liberasurecode
+# are registered. This also allows the vendored build to use the portable implementations of ISA-L.
+ISA_L_SYMBOLS="ec_encode_data ec_init_tables gf_gen_rs_matrix gf_gen_cauchy1_matrix gf_invert_matrix gf_mul"
+for lib in $STATIC_BACKENDS; do
+    nm -g lib/lib${lib}.a | awk '$2 == "T" { print $3 }' | sed "s/^${SYMBOL_PREFIX}//" | sort -u > static_symbols_${lib}.txt
+    if [[ $lib = isal ]]; then
+        grep -x -F "$(echo $ISA_L_SYMBOLS | tr ' ' '\n')" static_symbols_${lib}.txt > static_symbols_${lib}.tmp || true
+        mv static_symbols_${lib}.tmp static_symbols_${lib}.txt
+    fi
+done
+{
+    for lib in $STATIC_BACKENDS; do
+        echo "#ifdef STATIC_BACKEND_$(echo $lib | tr '[:lower:]' '[:upper:]')"
+        sed 's/.*/extern void &(void);/' static_symbols_${lib}.txt
+        echo '#endif'
+    done
+    echo 'static const struct static_symbol static_symbols[] = {'
+    for lib in $STATIC_BACKENDS; do
+        echo "#ifdef STATIC_BACKEND_$(echo $lib | tr '[:lower:]' '[:upper:]')"
+        sed 's/.*/    {"&", (void *)\&},/' static_symbols_${lib}.txt
+        echo '#endif'
+    done
+    echo '    {NULL, NULL},'
+    echo '};'
+} > static_symbols.h
+STATIC_BACKEND_FLAGS=""
+for lib in $STATIC_BACKENDS; do
+    STATIC_BACKEND_FLAGS="$STATIC_BACKEND_FLAGS -DSTATIC_BACKEND_$(echo $lib | tr '[:lower:]' '[:upper:]')"
+done
+if [[ $WITH_JERASURE = true ]]; then
+    STATIC_BACKEND_FLAGS="$STATIC_BACKEND_FLAGS -DSTATIC_BACKEND_JERASURE"
 fi
-patch -p1 < ../liberasurecode.patch # Applies a patch for building static library
-make $MAKE_FLAGS install
+${CC:-cc} -c -fPIC ${CFLAGS:-} $STATIC_BACKEND_FLAGS -I. static_backends.c -o static_backends.o
+ar rcs lib/liberasurecode_static.a static_backends.o
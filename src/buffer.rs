@@ -0,0 +1,218 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, Index};
+#[cfg(feature = "native")]
+use std::slice;
+
+#[cfg(feature = "native")]
+use crate::c_api;
+use crate::ErasureCoder;
+
+/// Fragments encoded by [`ErasureCoder::encode_borrowed`].
+///
+/// The fragments are located in the buffers allocated by [openstack/liberasurecode],
+/// and they are released when this is dropped.
+///
+/// [`ErasureCoder::encode_borrowed`]: ./struct.ErasureCoder.html#method.encode_borrowed
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+#[derive(Debug)]
+pub struct EncodedFragments<'a> {
+    buffers: EncodedBuffers,
+    data_fragments: usize,
+    parity_fragments: usize,
+    fragment_len: usize,
+    _coder: PhantomData<&'a ErasureCoder>,
+}
+#[derive(Debug)]
+enum EncodedBuffers {
+    #[cfg(feature = "native")]
+    Native {
+        desc: c_api::Desc,
+        encoded_data: *mut *mut u8,
+        encoded_parity: *mut *mut u8,
+    },
+    Owned(Vec<Vec<u8>>),
+}
+impl<'a> EncodedFragments<'a> {
+    /// Takes the ownership of the buffers returned by `c_api::encode`.
+    #[cfg(feature = "native")]
+    pub(crate) unsafe fn new(
+        coder: &'a ErasureCoder,
+        desc: c_api::Desc,
+        encoded_data: *mut *mut u8,
+        encoded_parity: *mut *mut u8,
+        fragment_len: u64,
+    ) -> Self {
+        EncodedFragments {
+            buffers: EncodedBuffers::Native {
+                desc,
+                encoded_data,
+                encoded_parity,
+            },
+            data_fragments: coder.data_fragments().get(),
+            parity_fragments: coder.parity_fragments().get(),
+            fragment_len: fragment_len as usize,
+            _coder: PhantomData,
+        }
+    }
+
+    /// Wraps the fragments encoded by `Backend::PureRustRs`.
+    pub(crate) fn from_vec(coder: &'a ErasureCoder, fragments: Vec<Vec<u8>>) -> Self {
+        EncodedFragments {
+            fragment_len: fragments[0].len(),
+            buffers: EncodedBuffers::Owned(fragments),
+            data_fragments: coder.data_fragments().get(),
+            parity_fragments: coder.parity_fragments().get(),
+            _coder: PhantomData,
+        }
+    }
+
+    /// Returns the number of the fragments.
+    pub fn len(&self) -> usize {
+        self.data_fragments + self.parity_fragments
+    }
+
+    /// Returns `true` if there are no fragments.
+    ///
+    /// This always returns `false` since a coder generates at least one data fragment.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the size of each fragment (including its header) in bytes.
+    pub fn fragment_len(&self) -> usize {
+        self.fragment_len
+    }
+
+    /// Returns the fragment specified by the given index.
+    ///
+    /// Indices in `0..data_fragments` are data fragments and the rest are parity fragments.
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        match self.buffers {
+            #[cfg(feature = "native")]
+            EncodedBuffers::Native {
+                encoded_data,
+                encoded_parity,
+                ..
+            } => {
+                let fragment = if index < self.data_fragments {
+                    unsafe { *encoded_data.add(index) }
+                } else if index < self.len() {
+                    unsafe { *encoded_parity.add(index - self.data_fragments) }
+                } else {
+                    return None;
+                };
+                Some(unsafe { slice::from_raw_parts(fragment, self.fragment_len) })
+            }
+            EncodedBuffers::Owned(ref fragments) => fragments.get(index).map(Vec::as_slice),
+        }
+    }
+
+    /// Returns an iterator over the fragments.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.len()).map(move |i| &self[i])
+    }
+}
+// The fragments are owned by this instance exclusively and are never modified.
+unsafe impl<'a> Send for EncodedFragments<'a> {}
+unsafe impl<'a> Sync for EncodedFragments<'a> {}
+impl<'a> Index<usize> for EncodedFragments<'a> {
+    type Output = [u8];
+
+    fn index(&self, index: usize) -> &[u8] {
+        self.get(index).expect("Fragment index out of range")
+    }
+}
+#[cfg(feature = "native")]
+impl<'a> Drop for EncodedFragments<'a> {
+    fn drop(&mut self) {
+        if let EncodedBuffers::Native {
+            desc,
+            encoded_data,
+            encoded_parity,
+        } = self.buffers
+        {
+            let _ = c_api::encode_cleanup(desc, encoded_data, encoded_parity);
+        }
+    }
+}
+
+/// Data decoded by [`ErasureCoder::decode_borrowed`].
+///
+/// The data is located in the buffer allocated by [openstack/liberasurecode],
+/// and it is released when this is dropped.
+///
+/// [`ErasureCoder::decode_borrowed`]: ./struct.ErasureCoder.html#method.decode_borrowed
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+#[derive(Debug)]
+pub struct DecodedData<'a> {
+    buffer: DecodedBuffer,
+    _coder: PhantomData<&'a ErasureCoder>,
+}
+#[derive(Debug)]
+enum DecodedBuffer {
+    #[cfg(feature = "native")]
+    Native {
+        desc: c_api::Desc,
+        data: *mut u8,
+        data_len: usize,
+    },
+    Owned(Vec<u8>),
+}
+impl<'a> DecodedData<'a> {
+    /// Takes the ownership of the buffer returned by `c_api::decode`.
+    #[cfg(feature = "native")]
+    pub(crate) unsafe fn new(
+        _coder: &'a ErasureCoder,
+        desc: c_api::Desc,
+        data: *mut u8,
+        data_len: u64,
+    ) -> Self {
+        DecodedData {
+            buffer: DecodedBuffer::Native {
+                desc,
+                data,
+                data_len: data_len as usize,
+            },
+            _coder: PhantomData,
+        }
+    }
+
+    /// Wraps the data decoded by `Backend::PureRustRs`.
+    pub(crate) fn from_vec(_coder: &'a ErasureCoder, data: Vec<u8>) -> Self {
+        DecodedData {
+            buffer: DecodedBuffer::Owned(data),
+            _coder: PhantomData,
+        }
+    }
+}
+// The data is owned by this instance exclusively and is never modified.
+unsafe impl<'a> Send for DecodedData<'a> {}
+unsafe impl<'a> Sync for DecodedData<'a> {}
+impl<'a> Deref for DecodedData<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self.buffer {
+            #[cfg(feature = "native")]
+            DecodedBuffer::Native { data, .. } if data.is_null() => &[],
+            #[cfg(feature = "native")]
+            DecodedBuffer::Native { data, data_len, .. } => unsafe {
+                slice::from_raw_parts(data, data_len)
+            },
+            DecodedBuffer::Owned(ref data) => data,
+        }
+    }
+}
+impl<'a> AsRef<[u8]> for DecodedData<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+#[cfg(feature = "native")]
+impl<'a> Drop for DecodedData<'a> {
+    fn drop(&mut self) {
+        if let DecodedBuffer::Native { desc, data, .. } = self.buffer {
+            let _ = c_api::decode_cleanup(desc, data);
+        }
+    }
+}
@@ -11,13 +11,7 @@ pub enum EcBackendId {
     ISA_L_RS_VAND = 4,
     SHSS = 5,
     LIBERASURECODE_RS_VAND = 6,
-}
-
-#[repr(C)]
-pub enum EcChecksumType {
-    NONE = 1,
-    CRC32 = 2,
-    MD5 = 3,
+    ISA_L_RS_CAUCHY = 7,
 }
 
 #[repr(C)]
@@ -27,27 +21,23 @@ pub struct EcArgs {
     pub w: c_int,
     pub hd: c_int,
     pub priv_args: [u64; 5],
-    pub ct: EcChecksumType,
+    /// One of `metadata::CHECKSUM_TYPE_*` (`ec_checksum_type_t`).
+    pub ct: c_int,
 }
 
 pub type Desc = c_int;
 pub type ErrorCode = c_uint;
 
-pub const EBACKENDNOTSUPP: u32 = 200;
-pub const EECMETHODNOTIMPL: u32 = 201;
-pub const EBACKENDINITERR: u32 = 202;
-pub const EBACKENDINUSE: u32 = 203;
-pub const EBACKENDNOTAVAIL: u32 = 204;
-pub const EBADCHKSUM: u32 = 205;
-pub const EINVALIDPARAMS: u32 = 206;
-pub const EBADHEADER: u32 = 207;
-pub const EINSUFFFRAGS: u32 = 208;
-
-#[link(name = "erasurecode", kind = "static")]
-#[link(name = "gf_complete", kind = "static")]
-#[link(name = "Jerasure", kind = "static")]
-#[link(name = "Xorcode", kind = "static")]
+// The libraries to be linked are specified by `build.rs`.
 extern "C" {
+    /// Checks if a given backend is available.
+    ///
+    /// @param backend_id - id of the backend to check
+    ///
+    /// @return 1 if a backend is available, 0 otherwise
+    ///
+    fn liberasurecode_backend_available(backend_id: EcBackendId) -> c_int;
+
     /// Create a liberasurecode instance and return a descriptor
     /// for use with EC operations (encode, decode, reconstruct)
     ///
@@ -181,16 +171,159 @@ extern "C" {
         destination_idx: c_int,
         out_fragment: *mut u8,
     ) -> Desc;
+
+    /// Verify that the specified pointer points to a well formed fragment that can
+    /// be processed by both this instance of liberasurecode and the specified
+    /// backend.
+    ///
+    /// @param desc - liberasurecode descriptor/handle
+    ///        from liberasurecode_instance_create()
+    /// @param fragment - fragment to verify
+    ///
+    /// @return 1 if fragment validation fails, 0 otherwise.
+    ///
+    fn is_invalid_fragment(desc: Desc, fragment: *const u8) -> c_int;
+
+    /// Verify a subset of fragments generated by encode()
+    ///
+    /// @param desc - liberasurecode descriptor/handle
+    ///        from liberasurecode_instance_create()
+    /// @param fragments - fragments part of the EC stripe to verify
+    /// @param num_fragments - number of fragments part of the EC stripe
+    ///
+    /// @return 0 on success, -error code otherwise
+    ///
+    fn liberasurecode_verify_stripe_metadata(
+        desc: Desc,
+        fragments: *const *const u8,
+        num_fragments: c_int,
+    ) -> c_int;
+
+    /// This computes the aligned size of a buffer passed into
+    /// the encode function.  The encode function must pad fragments
+    /// to be algined with the word size (w) and the last fragment also
+    /// needs to be aligned.  This computes the sum of the algined fragment
+    /// sizes for a given buffer to encode.
+    ///
+    /// @param desc - liberasurecode descriptor/handle
+    ///        from liberasurecode_instance_create()
+    /// @param data_len - original data length in bytes
+    ///
+    /// @return aligned length, or -error code on error
+    ///
+    fn liberasurecode_get_aligned_data_size(desc: Desc, data_len: u64) -> c_int;
+
+    /// This will return the minumum encode size, which is the minimum
+    /// buffer size that can be encoded.
+    ///
+    /// @param desc - liberasurecode descriptor/handle
+    ///        from liberasurecode_instance_create()
+    ///
+    /// @return minimum data length length, or -error code on error
+    ///
+    fn liberasurecode_get_minimum_encode_size(desc: Desc) -> c_int;
+
+    /// This will return the fragment size, which is each fragment data
+    /// length the backend will allocate when encoding.
+    ///
+    /// @param desc - liberasurecode descriptor/handle
+    ///        from liberasurecode_instance_create()
+    /// @param data_len - original data length in bytes
+    ///
+    /// @return fragment size - sizeof(fragment_header) + size
+    ///                         + frag_backend_metadata_size
+    ///
+    fn liberasurecode_get_fragment_size(desc: Desc, data_len: c_int) -> c_int;
+
+    /// Return a list of lists with valid rebuild indexes given
+    /// a list of missing indexes.
+    ///
+    /// @desc: liberasurecode instance descriptor (obtained with
+    ///        liberasurecode_instance_create)
+    /// @fragments_to_reconstruct list of indexes to reconstruct
+    /// @fragments_to_exclude list of indexes to exclude from
+    ///        reconstruction equation
+    /// @fragments_needed list of fragments needed to reconstruct
+    ///        fragments in fragments_to_reconstruct
+    ///
+    /// @return 0 on success, non-zero on error
+    ///
+    fn liberasurecode_fragments_needed(
+        desc: Desc,
+        fragments_to_reconstruct: *const c_int,
+        fragments_to_exclude: *const c_int,
+        fragments_needed: *mut c_int,
+    ) -> c_int;
+
+    /// Initializes the default GF(2^w) field of Jerasure if it has not been initialized yet.
+    ///
+    /// @return 0 on success, or error code (ENOMEM or EINVAL) on failure
+    ///
+    #[cfg(all(feature = "jerasure", not(liberasurecode_system)))]
+    fn galois_init_default_field(w: c_int) -> c_int;
+}
+
+pub fn backend_available(id: EcBackendId) -> bool {
+    let _guard = lock_backend();
+    unsafe { liberasurecode_backend_available(id) == 1 }
 }
 
 pub fn instance_create(id: EcBackendId, args: &EcArgs) -> Result<Desc, ErrorCode> {
+    init_galois_fields();
+    let _guard = lock_backend();
     match unsafe { liberasurecode_instance_create(id, args) } {
         desc if desc > 0 => Ok(desc),
         code => Err(-code as ErrorCode),
     }
 }
 
+/// Initializes the GF fields shared by all the Jerasure based instances.
+///
+/// Jerasure initializes the fields lazily without any synchronization,
+/// so concurrent creations of instances may observe half-initialized fields.
+/// To prevent it, the fields for all the word sizes used by the backends are initialized
+/// up front. They are never released afterwards (see `liberasurecode.patch`).
+#[cfg(all(feature = "jerasure", not(liberasurecode_system)))]
+fn init_galois_fields() {
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        for &w in &[8, 16, 32] {
+            let code = unsafe { galois_init_default_field(w) };
+            assert_eq!(code, 0, "Cannot initialize GF(2^{})", w);
+        }
+    });
+}
+
+/// A system-installed liberasurecode loads Jerasure by itself, so the fields cannot be initialized
+/// in advance. Without the `jerasure` feature, Jerasure is not linked at all.
+#[cfg(not(all(feature = "jerasure", not(liberasurecode_system))))]
+fn init_galois_fields() {}
+
+/// Serializes all the calls to liberasurecode.
+///
+/// A system-installed liberasurecode is not patched by `liberasurecode.patch`,
+/// so destroying a Jerasure based instance releases the GF fields shared by all the instances
+/// (and Jerasure initializes them again lazily without any synchronization).
+/// Holding this lock while calling liberasurecode ensures that no instance is using the fields
+/// while they are released or initialized.
+#[cfg(liberasurecode_system)]
+fn lock_backend() -> Option<std::sync::MutexGuard<'static, ()>> {
+    use std::sync::Mutex;
+
+    static LOCK: Mutex<()> = Mutex::new(());
+    Some(LOCK.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// The bundled liberasurecode can be called concurrently.
+#[cfg(not(liberasurecode_system))]
+fn lock_backend() -> Option<std::sync::MutexGuard<'static, ()>> {
+    None
+}
+
 pub fn instance_destroy(desc: Desc) -> Result<(), ErrorCode> {
+    let _guard = lock_backend();
     match unsafe { liberasurecode_instance_destroy(desc) } {
         0 => Ok(()),
         code => Err(code as ErrorCode),
@@ -201,6 +334,7 @@ pub fn encode(
     desc: Desc,
     orig_data: &[u8],
 ) -> Result<(*mut *mut u8, *mut *mut u8, u64), ErrorCode> {
+    let _guard = lock_backend();
     let mut encoded_data = ptr::null_mut();
     let mut encoded_parity = ptr::null_mut();
     let mut fragment_len = 0;
@@ -225,6 +359,7 @@ pub fn encode_cleanup(
     encoded_data: *mut *mut u8,
     encoded_parity: *mut *mut u8,
 ) -> Result<(), ErrorCode> {
+    let _guard = lock_backend();
     match unsafe { liberasurecode_encode_cleanup(desc, encoded_data, encoded_parity) } {
         0 => Ok(()),
         code => Err(-code as ErrorCode),
@@ -238,6 +373,7 @@ pub fn decode(
 ) -> Result<(*mut u8, u64), ErrorCode> {
     assert!(!fragments.is_empty());
 
+    let _guard = lock_backend();
     let mut out_data = ptr::null_mut();
     let mut out_data_len = 0;
     let result = unsafe {
@@ -262,6 +398,7 @@ pub fn decode(
 }
 
 pub fn decode_cleanup(desc: Desc, data: *mut u8) -> Result<(), ErrorCode> {
+    let _guard = lock_backend();
     match unsafe { liberasurecode_decode_cleanup(desc, data) } {
         0 => Ok(()),
         code => Err(-code as ErrorCode),
@@ -274,6 +411,7 @@ pub fn reconstruct_fragment(
     destination_idx: usize,
 ) -> Result<Vec<u8>, ErrorCode> {
     assert!(!available_fragments.is_empty());
+    let _guard = lock_backend();
     let mut buf = vec![0; available_fragments[0].len()];
     let result = unsafe {
         liberasurecode_reconstruct_fragment(
@@ -294,3 +432,92 @@ pub fn reconstruct_fragment(
         _ => Err(-result as ErrorCode),
     }
 }
+
+/// Returns the indices of the fragments needed to reconstruct `to_reconstruct`
+/// without using `to_exclude`.
+///
+/// `fragments` is the total number of data and parity fragments.
+pub fn fragments_needed(
+    desc: Desc,
+    to_reconstruct: &[usize],
+    to_exclude: &[usize],
+    fragments: usize,
+) -> Result<Vec<usize>, ErrorCode> {
+    // The lists are terminated by `-1`.
+    let to_index_list = |indices: &[usize]| {
+        indices
+            .iter()
+            .map(|&i| i as c_int)
+            .chain(Some(-1))
+            .collect::<Vec<_>>()
+    };
+    let to_reconstruct = to_index_list(to_reconstruct);
+    let to_exclude = to_index_list(to_exclude);
+    let mut needed: Vec<c_int> = vec![-1; fragments + 1];
+    let _guard = lock_backend();
+    let result = unsafe {
+        liberasurecode_fragments_needed(
+            desc,
+            to_reconstruct.as_ptr(),
+            to_exclude.as_ptr(),
+            needed.as_mut_ptr(),
+        )
+    };
+    match result {
+        0 => Ok(needed
+            .into_iter()
+            .take_while(|&i| i >= 0)
+            .map(|i| i as usize)
+            .collect()),
+        _ => Err(-result as ErrorCode),
+    }
+}
+
+pub fn is_valid_fragment(desc: Desc, fragment: &[u8]) -> bool {
+    let _guard = lock_backend();
+    unsafe { is_invalid_fragment(desc, fragment.as_ptr()) == 0 }
+}
+
+pub fn verify_stripe_metadata(desc: Desc, fragments: &[&[u8]]) -> Result<(), ErrorCode> {
+    let _guard = lock_backend();
+    let result = unsafe {
+        liberasurecode_verify_stripe_metadata(
+            desc,
+            fragments
+                .iter()
+                .map(|x| x.as_ptr())
+                .collect::<Vec<_>>()
+                .as_ptr(),
+            fragments.len() as c_int,
+        )
+    };
+    match result {
+        0 => Ok(()),
+        _ => Err(-result as ErrorCode),
+    }
+}
+
+pub fn get_aligned_data_size(desc: Desc, data_len: u64) -> Result<u64, ErrorCode> {
+    let _guard = lock_backend();
+    match unsafe { liberasurecode_get_aligned_data_size(desc, data_len) } {
+        size if size >= 0 => Ok(size as u64),
+        code => Err(-code as ErrorCode),
+    }
+}
+
+pub fn get_minimum_encode_size(desc: Desc) -> Result<u64, ErrorCode> {
+    let _guard = lock_backend();
+    match unsafe { liberasurecode_get_minimum_encode_size(desc) } {
+        size if size >= 0 => Ok(size as u64),
+        code => Err(-code as ErrorCode),
+    }
+}
+
+/// Returns the size of a fragment excluding its header.
+pub fn get_fragment_size(desc: Desc, data_len: c_int) -> Result<u64, ErrorCode> {
+    let _guard = lock_backend();
+    match unsafe { liberasurecode_get_fragment_size(desc, data_len) } {
+        size if size >= 0 => Ok(size as u64),
+        code => Err(-code as ErrorCode),
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+
+use crate::{Builder, ErasureCoder, Result};
+
+/// A cache of [`ErasureCoder`]s keyed by their configurations.
+///
+/// This keeps at most one coder (i.e., one [openstack/liberasurecode] descriptor)
+/// per distinct combination of the data fragments, the parity fragments, the backend
+/// and the checksum algorithm, and hands it out as cheap clonable `Arc` handles.
+///
+/// The cache only holds weak references to the coders,
+/// so a coder is destroyed when the last handle to it is dropped.
+///
+/// [`ErasureCoder`]: ./struct.ErasureCoder.html
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+///
+/// # Examples
+///
+/// ```
+/// use liberasurecode::{Builder, CoderCache};
+/// use std::num::NonZeroUsize;
+/// use std::sync::Arc;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let data_fragments = NonZeroUsize::new(4).ok_or("too few fragments")?;
+/// let parity_fragments = NonZeroUsize::new(2).ok_or("too few fragments")?;
+/// let builder = Builder::new(data_fragments, parity_fragments);
+///
+/// let cache = CoderCache::new();
+/// let coder0 = cache.get(&builder)?;
+/// let coder1 = cache.get(&builder)?;
+/// assert!(Arc::ptr_eq(&coder0, &coder1));
+/// assert_eq!(cache.len(), 1);
+///
+/// std::mem::drop((coder0, coder1));
+/// assert_eq!(cache.len(), 0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct CoderCache {
+    coders: Mutex<HashMap<Builder, Weak<ErasureCoder>>>,
+}
+impl CoderCache {
+    /// Makes a new empty `CoderCache`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the coder configured by the given builder.
+    ///
+    /// If there is no live coder for the configuration, a new one is created
+    /// by [`Builder::finish`] and cached.
+    ///
+    /// The cache is not locked while creating a coder, so a slow (or panicking) creation does not
+    /// block (or poison) the other callers. If multiple threads create a coder for the same
+    /// configuration at the same time, only the first one to finish is cached and returned to all of them.
+    ///
+    /// [`Builder::finish`]: ./struct.Builder.html#method.finish
+    pub fn get(&self, builder: &Builder) -> Result<Arc<ErasureCoder>> {
+        if let Some(coder) = self.lock().get(builder).and_then(Weak::upgrade) {
+            return Ok(coder);
+        }
+
+        let coder = Arc::new(builder.finish()?);
+        let mut coders = self.lock();
+        if let Some(cached) = coders.get(builder).and_then(Weak::upgrade) {
+            // Another thread has created a coder for the configuration in the meantime.
+            return Ok(cached);
+        }
+        coders.retain(|_, coder| coder.strong_count() > 0);
+        coders.insert(builder.clone(), Arc::downgrade(&coder));
+        Ok(coder)
+    }
+
+    /// Returns the number of the live coders in this cache.
+    pub fn len(&self) -> usize {
+        self.lock()
+            .values()
+            .filter(|coder| coder.strong_count() > 0)
+            .count()
+    }
+
+    /// Returns `true` if this cache has no live coders, otherwise `false`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Builder, Weak<ErasureCoder>>> {
+        // The map is always left consistent (only `retain` and `insert` are called while locked),
+        // so it is safe to keep using it even if a thread has panicked while holding the lock.
+        self.coders
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns the process-wide cache used by [`ErasureCoder::shared`].
+    ///
+    /// [`ErasureCoder::shared`]: ./struct.ErasureCoder.html#method.shared
+    pub(crate) fn global() -> &'static CoderCache {
+        static CACHE: OnceLock<CoderCache> = OnceLock::new();
+        CACHE.get_or_init(CoderCache::new)
+    }
+}
@@ -0,0 +1,37 @@
+/// The lookup table of the CRC-32 polynomial `0xedb88320` (reflected).
+static TABLE: [u32; 256] = build_table();
+
+const fn build_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            c = if c & 1 != 0 {
+                (c >> 1) ^ 0xedb8_8320
+            } else {
+                c >> 1
+            };
+            j += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// Computes the CRC32 checksum used by [openstack/liberasurecode] for fragment headers and payloads.
+///
+/// This is equivalent to `crc32(0, bytes, bytes.len())` of liberasurecode 1.5.0, which is *not*
+/// the standard CRC-32 (e.g., of zlib): the intermediate value is held in a signed `int`,
+/// so it is shifted arithmetically (i.e., the sign bit is propagated).
+///
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0i32;
+    for &b in bytes {
+        crc = (TABLE[((crc ^ i32::from(b)) & 0xff) as usize] as i32) ^ (crc >> 8);
+    }
+    !crc as u32
+}
@@ -0,0 +1,305 @@
+use std::sync::OnceLock;
+
+/// The primitive polynomial `x^8 + x^4 + x^3 + x^2 + 1` generating the field.
+const POLYNOMIAL: u16 = 0x11d;
+
+/// Exponent and logarithm tables of the generator `2`.
+struct Tables {
+    /// `exp[i] = 2^i` (doubled to avoid the reduction modulo 255 in `mul`).
+    exp: [u8; 512],
+
+    /// `log[2^i] = i` (`log[0]` is undefined).
+    log: [u8; 256],
+}
+
+static TABLES: Tables = build_tables();
+
+const fn build_tables() -> Tables {
+    let mut exp = [0; 512];
+    let mut log = [0; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= POLYNOMIAL;
+        }
+        i += 1;
+    }
+    Tables { exp, log }
+}
+
+/// Returns `a * b`.
+pub(crate) fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + TABLES.log[b as usize] as usize]
+}
+
+/// Returns the multiplicative inverse of `a`.
+///
+/// # Panics
+///
+/// Panics if `a` is zero.
+pub(crate) fn inv(a: u8) -> u8 {
+    assert_ne!(a, 0, "Zero has no inverse");
+    TABLES.exp[255 - TABLES.log[a as usize] as usize]
+}
+
+/// Computes `dst[i] ^= c * src[i]` for every byte.
+///
+/// The fastest kernel supported by the CPU is used (see `Kernel::best`).
+pub(crate) fn mul_add_slice(c: u8, src: &[u8], dst: &mut [u8]) {
+    Kernel::best().mul_add_slice(c, src, dst);
+}
+
+/// Implementations of `mul_add_slice`.
+///
+/// The SIMD kernels split each byte into nibbles and look the products up in two 16-byte tables
+/// by the byte shuffle instructions (`pshufb` on x86, `tbl` on AArch64).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kernel {
+    Scalar,
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Ssse3,
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Avx2,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+}
+impl Kernel {
+    /// Returns the kernels supported by the CPU in ascending order of speed.
+    pub fn available() -> Vec<Kernel> {
+        #[allow(unused_mut)]
+        let mut kernels = vec![Kernel::Scalar];
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("ssse3") {
+                kernels.push(Kernel::Ssse3);
+            }
+            if is_x86_feature_detected!("avx2") {
+                kernels.push(Kernel::Avx2);
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                kernels.push(Kernel::Neon);
+            }
+        }
+        kernels
+    }
+
+    /// Returns the fastest kernel supported by the CPU.
+    pub fn best() -> Kernel {
+        static BEST: OnceLock<Kernel> = OnceLock::new();
+        *BEST.get_or_init(|| *Kernel::available().last().expect("Never fails"))
+    }
+
+    /// Computes `dst[i] ^= c * src[i]` for every byte by this kernel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lengths of `src` and `dst` differ.
+    pub fn mul_add_slice(self, c: u8, src: &[u8], dst: &mut [u8]) {
+        assert_eq!(src.len(), dst.len());
+        if c == 0 {
+            return;
+        }
+
+        let tables = NibbleTables::new(c);
+        let processed = match self {
+            Kernel::Scalar => 0,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Ssse3 => unsafe { x86::mul_add_ssse3(&tables, src, dst) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Avx2 => unsafe { x86::mul_add_avx2(&tables, src, dst) },
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => unsafe { aarch64::mul_add_neon(&tables, src, dst) },
+        };
+        mul_add_scalar(c, &src[processed..], &mut dst[processed..]);
+    }
+}
+
+/// The products of a coefficient and the low and high nibbles.
+struct NibbleTables {
+    low: [u8; 16],
+    high: [u8; 16],
+}
+impl NibbleTables {
+    fn new(c: u8) -> Self {
+        let mut low = [0; 16];
+        let mut high = [0; 16];
+        for x in 0..16 {
+            low[x] = mul(c, x as u8);
+            high[x] = mul(c, (x as u8) << 4);
+        }
+        NibbleTables { low, high }
+    }
+}
+
+fn mul_add_scalar(c: u8, src: &[u8], dst: &mut [u8]) {
+    if c == 1 {
+        for (d, s) in dst.iter_mut().zip(src) {
+            *d ^= *s;
+        }
+        return;
+    }
+
+    let mut table = [0; 256];
+    for (x, product) in table.iter_mut().enumerate() {
+        *product = mul(c, x as u8);
+    }
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= table[*s as usize];
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    use super::NibbleTables;
+
+    /// Processes the longest prefix whose length is a multiple of 16,
+    /// and returns the length of the prefix.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support SSSE3, and `dst` must be at least as long as `src`.
+    #[target_feature(enable = "ssse3")]
+    pub unsafe fn mul_add_ssse3(tables: &NibbleTables, src: &[u8], dst: &mut [u8]) -> usize {
+        let low = _mm_loadu_si128(tables.low.as_ptr() as *const __m128i);
+        let high = _mm_loadu_si128(tables.high.as_ptr() as *const __m128i);
+        let mask = _mm_set1_epi8(0x0f);
+
+        let len = src.len() / 16 * 16;
+        for i in (0..len).step_by(16) {
+            let s = _mm_loadu_si128(src.as_ptr().add(i) as *const __m128i);
+            let d = _mm_loadu_si128(dst.as_ptr().add(i) as *const __m128i);
+            let s_low = _mm_and_si128(s, mask);
+            let s_high = _mm_and_si128(_mm_srli_epi64(s, 4), mask);
+            let product =
+                _mm_xor_si128(_mm_shuffle_epi8(low, s_low), _mm_shuffle_epi8(high, s_high));
+            _mm_storeu_si128(
+                dst.as_mut_ptr().add(i) as *mut __m128i,
+                _mm_xor_si128(d, product),
+            );
+        }
+        len
+    }
+
+    /// Processes the longest prefix whose length is a multiple of 32,
+    /// and returns the length of the prefix.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2, and `dst` must be at least as long as `src`.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn mul_add_avx2(tables: &NibbleTables, src: &[u8], dst: &mut [u8]) -> usize {
+        // `vpshufb` looks up each 128-bit lane independently, so the tables are duplicated.
+        let low =
+            _mm256_broadcastsi128_si256(_mm_loadu_si128(tables.low.as_ptr() as *const __m128i));
+        let high =
+            _mm256_broadcastsi128_si256(_mm_loadu_si128(tables.high.as_ptr() as *const __m128i));
+        let mask = _mm256_set1_epi8(0x0f);
+
+        let len = src.len() / 32 * 32;
+        for i in (0..len).step_by(32) {
+            let s = _mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i);
+            let d = _mm256_loadu_si256(dst.as_ptr().add(i) as *const __m256i);
+            let s_low = _mm256_and_si256(s, mask);
+            let s_high = _mm256_and_si256(_mm256_srli_epi64(s, 4), mask);
+            let product = _mm256_xor_si256(
+                _mm256_shuffle_epi8(low, s_low),
+                _mm256_shuffle_epi8(high, s_high),
+            );
+            _mm256_storeu_si256(
+                dst.as_mut_ptr().add(i) as *mut __m256i,
+                _mm256_xor_si256(d, product),
+            );
+        }
+        len
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use std::arch::aarch64::*;
+
+    use super::NibbleTables;
+
+    /// Processes the longest prefix whose length is a multiple of 16,
+    /// and returns the length of the prefix.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support NEON, and `dst` must be at least as long as `src`.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn mul_add_neon(tables: &NibbleTables, src: &[u8], dst: &mut [u8]) -> usize {
+        let low = vld1q_u8(tables.low.as_ptr());
+        let high = vld1q_u8(tables.high.as_ptr());
+        let mask = vdupq_n_u8(0x0f);
+
+        let len = src.len() / 16 * 16;
+        for i in (0..len).step_by(16) {
+            let s = vld1q_u8(src.as_ptr().add(i));
+            let d = vld1q_u8(dst.as_ptr().add(i));
+            let product = veorq_u8(
+                vqtbl1q_u8(low, vandq_u8(s, mask)),
+                vqtbl1q_u8(high, vshrq_n_u8(s, 4)),
+            );
+            vst1q_u8(dst.as_mut_ptr().add(i), veorq_u8(d, product));
+        }
+        len
+    }
+}
+
+/// Inverts the given square matrix in place by Gauss-Jordan elimination.
+///
+/// Returns `false` if the matrix is singular (the contents are unspecified in that case).
+pub(crate) fn invert_matrix(matrix: &mut [Vec<u8>]) -> bool {
+    let n = matrix.len();
+    let mut inverse = (0..n)
+        .map(|i| {
+            let mut row = vec![0; n];
+            row[i] = 1;
+            row
+        })
+        .collect::<Vec<_>>();
+
+    for col in 0..n {
+        let pivot = match (col..n).find(|&row| matrix[row][col] != 0) {
+            None => return false,
+            Some(pivot) => pivot,
+        };
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = inv(matrix[col][col]);
+        for j in 0..n {
+            matrix[col][j] = mul(matrix[col][j], scale);
+            inverse[col][j] = mul(inverse[col][j], scale);
+        }
+        for row in 0..n {
+            let factor = matrix[row][col];
+            if row == col || factor == 0 {
+                continue;
+            }
+            for j in 0..n {
+                let (m, i) = (matrix[col][j], inverse[col][j]);
+                matrix[row][j] ^= mul(factor, m);
+                inverse[row][j] ^= mul(factor, i);
+            }
+        }
+    }
+    matrix.swap_with_slice(&mut inverse);
+    true
+}
@@ -18,6 +18,17 @@
 //! $ sudo apt install gcc git make automake autoconf libtool
 //! ```
 //!
+//! The `isa-l` feature additionally requires `nasm` (`>= 2.14`) on x86_64.
+//!
+//! Alternatively, setting the `LIBERASURECODE_SYSTEM=1` environment variable makes the build script
+//! link [openstack/liberasurecode] (`>= 1.5.0, < 2.0.0`) installed in the system via `pkg-config`
+//! instead of building it. See [README] for details.
+//!
+//! Without the `native` feature (i.e., with `default-features = false`), nothing is built or linked
+//! and only [`Backend::PureRustRs`] is available.
+//!
+//! [README]: https://github.com/frugalos/liberasurecode/blob/master/README.md
+//!
 //!
 //! # Examples
 //!
@@ -29,7 +40,7 @@
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! let data_fragments = NonZeroUsize::new(4).ok_or("too few fragments")?;
 //! let parity_fragments = NonZeroUsize::new(2).ok_or("too few fragments")?;
-//! let mut coder = ErasureCoder::new(data_fragments, parity_fragments)?;
+//! let coder = ErasureCoder::new(data_fragments, parity_fragments)?;
 //! let input = vec![0, 1, 2, 3];
 //!
 //! // Encodes `input` to data and parity fragments
@@ -44,33 +55,255 @@
 //! # }
 //! ```
 #![warn(missing_docs)]
+#[cfg(feature = "native")]
 extern crate libc;
 
 use std::num::NonZeroUsize;
-use std::slice;
-use std::time::Duration;
+use std::os::raw::c_int;
+use std::sync::Arc;
 
+pub use crate::buffer::{DecodedData, EncodedFragments};
+pub use crate::cache::CoderCache;
+pub use crate::metadata::FragmentMetadata;
 pub use crate::result::{Error, Result};
+pub use crate::verify::{FragmentError, StripeReport};
 
+mod buffer;
+#[cfg(feature = "native")]
 mod c_api;
+mod cache;
+mod crc32;
+mod gf;
+mod metadata;
+mod pure_rust;
 mod result;
+mod verify;
 
 /// Erasure coding backends that can be used for encoding and decoding data.
+///
+/// Some of the variants are only defined when the corresponding cargo features are enabled:
+/// - `native`: `LiberasurecodeRsVand` and `Null`
+/// - `jerasure`: `JerasureRsVand` and `JerasureRsCauchy`
+/// - `flat-xor`: `FlatXorHd`
+/// - `isa-l`: `IsaLRsVand` and `IsaLRsCauchy`
+///
+/// The `native`, `jerasure` and `flat-xor` features are enabled by default.
+/// The other features imply `native`. Only `PureRustRs` is always defined.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum Backend {
     /// Read-Solomon erasure coding provided by `jerasure` library.
+    #[cfg(feature = "jerasure")]
     JerasureRsVand,
 
     /// Cauchy base Read-Solomon erasure coding provided by `jerasure` library (default).
+    #[cfg(feature = "jerasure")]
     JerasureRsCauchy,
+
+    /// Flat XOR erasure coding provided by `liberasurecode` itself.
+    ///
+    /// The supported parameters are limited to the following combinations:
+    /// - `parity_fragments = 3` and `data_fragments = 3` (hamming distance is 3)
+    /// - `parity_fragments = 5` and `5 <= data_fragments <= 10` (hamming distance is 4)
+    /// - `parity_fragments = 6` and `6 <= data_fragments <= 20` (hamming distance is 4)
+    #[cfg(feature = "flat-xor")]
+    FlatXorHd,
+
+    /// Read-Solomon erasure coding provided by Intel's `isa-l` library.
+    ///
+    /// `parity_fragments` must be less than or equal to 4
+    /// because larger values may result in undecodable fragment combinations.
+    #[cfg(feature = "isa-l")]
+    IsaLRsVand,
+
+    /// Cauchy base Read-Solomon erasure coding provided by Intel's `isa-l` library.
+    #[cfg(feature = "isa-l")]
+    IsaLRsCauchy,
+
+    /// Read-Solomon erasure coding provided by `liberasurecode` itself.
+    ///
+    /// This is the default if the `jerasure` feature is disabled.
+    #[cfg(feature = "native")]
+    LiberasurecodeRsVand,
+
+    /// Null backend which does not actually generate parity fragments.
+    ///
+    /// This is only useful for testing and measuring the overhead of `liberasurecode`.
+    #[cfg(feature = "native")]
+    Null,
+
+    /// Cauchy base Read-Solomon erasure coding over GF(2^8) implemented in Rust.
+    ///
+    /// The fragments have the same header layout as those of the other backends,
+    /// but they can be decoded only by this backend.
+    /// This is the default if the `native` feature is disabled.
+    /// `data_fragments + parity_fragments` must be less than or equal to 256.
+    PureRustRs,
+}
+impl Backend {
+    /// Returns an iterator over all the backends.
+    ///
+    /// Note that some of the backends may not be available in the current environment.
+    pub fn all() -> impl Iterator<Item = Backend> {
+        [
+            #[cfg(feature = "jerasure")]
+            Backend::JerasureRsVand,
+            #[cfg(feature = "jerasure")]
+            Backend::JerasureRsCauchy,
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd,
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsVand,
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsCauchy,
+            #[cfg(feature = "native")]
+            Backend::LiberasurecodeRsVand,
+            #[cfg(feature = "native")]
+            Backend::Null,
+            Backend::PureRustRs,
+        ]
+        .iter()
+        .cloned()
+    }
+
+    /// Returns `true` if the backend is available in the current environment, otherwise `false`.
+    pub fn is_available(self) -> bool {
+        #[cfg(feature = "native")]
+        {
+            if let Some(id) = self.id() {
+                return c_api::backend_available(id);
+            }
+        }
+        true
+    }
+
+    /// Returns the ID of the backend in [openstack/liberasurecode].
+    ///
+    /// `None` is returned if the backend is implemented by this crate.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    #[cfg(feature = "native")]
+    fn id(self) -> Option<c_api::EcBackendId> {
+        let id = match self {
+            #[cfg(feature = "jerasure")]
+            Backend::JerasureRsVand => c_api::EcBackendId::JERASURE_RS_VAND,
+            #[cfg(feature = "jerasure")]
+            Backend::JerasureRsCauchy => c_api::EcBackendId::JERASURE_RS_CAUCHY,
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd => c_api::EcBackendId::FLAT_XOR_HD,
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsVand => c_api::EcBackendId::ISA_L_RS_VAND,
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsCauchy => c_api::EcBackendId::ISA_L_RS_CAUCHY,
+            Backend::LiberasurecodeRsVand => c_api::EcBackendId::LIBERASURECODE_RS_VAND,
+            Backend::Null => c_api::EcBackendId::NULL,
+            Backend::PureRustRs => return None,
+        };
+        Some(id)
+    }
+
+    /// Returns the backend ID recorded in the headers of fragments.
+    #[cfg(feature = "native")]
+    fn header_id(self) -> u8 {
+        self.id().map_or(pure_rust::BACKEND_ID, |id| id as u8)
+    }
+
+    /// Returns the backend ID recorded in the headers of fragments.
+    #[cfg(not(feature = "native"))]
+    fn header_id(self) -> u8 {
+        match self {
+            Backend::PureRustRs => pure_rust::BACKEND_ID,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        Backend::all().find(|backend| backend.header_id() == id)
+    }
+
+    /// Returns the word size (in bits) used by the backend.
+    fn word_size(self) -> usize {
+        match self {
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsVand | Backend::IsaLRsCauchy => 8,
+            #[cfg(feature = "native")]
+            Backend::LiberasurecodeRsVand => 16,
+            Backend::PureRustRs => 8,
+            #[allow(unreachable_patterns)]
+            _ => 32,
+        }
+    }
+
+    /// Returns `true` if any `data_fragments` fragments suffice to decode (and vice versa).
+    #[cfg(feature = "native")]
+    fn is_mds(self) -> bool {
+        match self {
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd => false,
+            Backend::Null => false,
+            _ => true,
+        }
+    }
+
+    /// Returns the hamming distance passed to the backend.
+    #[cfg(feature = "native")]
+    fn hamming_distance(self, parity_fragments: usize) -> usize {
+        match self {
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd if parity_fragments == 3 => 3,
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd => 4,
+            _ => parity_fragments,
+        }
+    }
+
+    /// Checks whether the backend supports the given parameters.
+    fn validate_params(self, data_fragments: usize, parity_fragments: usize) -> Result<()> {
+        let k = data_fragments;
+        let m = parity_fragments;
+        if k == 1 && m == 1 {
+            // Using this parameters, some backend will abort during executing `reconstruct` function.
+            return Err(Error::InvalidParams);
+        }
+
+        let is_valid = match self {
+            #[cfg(feature = "jerasure")]
+            Backend::JerasureRsVand | Backend::JerasureRsCauchy => true,
+            #[cfg(feature = "native")]
+            Backend::Null => true,
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd => match m {
+                3 => k == 3,
+                5 => (5..=10).contains(&k),
+                6 => (6..=20).contains(&k),
+                _ => false,
+            },
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsVand => m <= 4 && k + m <= 1 << self.word_size(),
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsCauchy => k + m <= 1 << self.word_size(),
+            #[cfg(feature = "native")]
+            Backend::LiberasurecodeRsVand => k + m <= 1 << self.word_size(),
+            Backend::PureRustRs => k + m <= 1 << self.word_size(),
+        };
+        if is_valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidParams)
+        }
+    }
 }
 impl Default for Backend {
-    /// `Backend::JerasureRsCauchy`を返す.
+    /// `Builder::DEFAULT_BACKEND`を返す.
     fn default() -> Self {
-        Backend::JerasureRsCauchy
+        Builder::DEFAULT_BACKEND
     }
 }
 
+/// Returns an iterator over the backends available in the current environment.
+pub fn available_backends() -> impl Iterator<Item = Backend> {
+    Backend::all().filter(|backend| backend.is_available())
+}
+
 /// Available checksum algorithms for validating decoded data.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Checksum {
@@ -83,6 +316,16 @@ pub enum Checksum {
     /// MD5.
     Md5,
 }
+impl Checksum {
+    /// Returns the value of `ec_checksum_type_t` recorded in the headers of fragments.
+    fn id(self) -> u8 {
+        match self {
+            Checksum::None => metadata::CHECKSUM_TYPE_NONE,
+            Checksum::Crc32 => metadata::CHECKSUM_TYPE_CRC32,
+            Checksum::Md5 => metadata::CHECKSUM_TYPE_MD5,
+        }
+    }
+}
 impl Default for Checksum {
     /// `Checksum::None`を返す.
     fn default() -> Self {
@@ -93,7 +336,7 @@ impl Default for Checksum {
 /// [`ErasureCoder`] builder.
 ///
 /// [`ErasureCoder`]: ./struct.ErasureCoder.html
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Builder {
     data_fragments: NonZeroUsize,
     parity_fragments: NonZeroUsize,
@@ -102,8 +345,17 @@ pub struct Builder {
 }
 impl Builder {
     /// The default backend.
+    #[cfg(feature = "jerasure")]
     pub const DEFAULT_BACKEND: Backend = Backend::JerasureRsCauchy;
 
+    /// The default backend.
+    #[cfg(all(feature = "native", not(feature = "jerasure")))]
+    pub const DEFAULT_BACKEND: Backend = Backend::LiberasurecodeRsVand;
+
+    /// The default backend.
+    #[cfg(not(feature = "native"))]
+    pub const DEFAULT_BACKEND: Backend = Backend::PureRustRs;
+
     /// The default checksum algorithm.
     pub const DEFAULT_CHECKSUM: Checksum = Checksum::None;
 
@@ -140,49 +392,158 @@ impl Builder {
     ///
     /// [`ErasureCoder`]: ./struct.ErasureCoder.html
     pub fn finish(&self) -> Result<ErasureCoder> {
-        let backend_id = match self.backend {
-            Backend::JerasureRsCauchy => c_api::EcBackendId::JERASURE_RS_CAUCHY,
-            Backend::JerasureRsVand => c_api::EcBackendId::JERASURE_RS_VAND,
-        };
-        let checksum_type = match self.checksum {
-            Checksum::None => c_api::EcChecksumType::NONE,
-            Checksum::Crc32 => c_api::EcChecksumType::CRC32,
-            Checksum::Md5 => c_api::EcChecksumType::MD5,
-        };
-        let ec_args = c_api::EcArgs {
-            k: self.data_fragments.get() as libc::c_int,
-            m: self.parity_fragments.get() as libc::c_int,
-            w: 32,
-            hd: self.parity_fragments.get() as libc::c_int,
-            priv_args: [0; 5],
-            ct: checksum_type,
-        };
+        let k = self.data_fragments.get();
+        let m = self.parity_fragments.get();
+        self.backend.validate_params(k, m)?;
 
-        if self.data_fragments.get() == 1 && self.parity_fragments.get() == 1 {
-            // Using this parameters, some backend will abort during executing `reconstruct` function.
-            return Err(Error::InvalidParams);
+        #[cfg(feature = "native")]
+        {
+            if let Some(backend_id) = self.backend.id() {
+                let ec_args = c_api::EcArgs {
+                    k: k as libc::c_int,
+                    m: m as libc::c_int,
+                    w: self.backend.word_size() as libc::c_int,
+                    hd: self.backend.hamming_distance(m) as libc::c_int,
+                    priv_args: [0; 5],
+                    ct: libc::c_int::from(self.checksum.id()),
+                };
+                return c_api::instance_create(backend_id, &ec_args)
+                    .map(|desc| self.new_coder(Engine::Native(desc)))
+                    .map_err(Error::from_error_code);
+            }
         }
+        let engine = Engine::PureRust(pure_rust::ReedSolomon::new(k, m, self.checksum));
+        Ok(self.new_coder(engine))
+    }
 
-        // The creation of coder instance is not thread-safe, so we protect it by the global lock.
-        with_global_lock(|| {
-            let coder = c_api::instance_create(backend_id, &ec_args)
-                .map(|desc| ErasureCoder {
-                    data_fragments: self.data_fragments,
-                    parity_fragments: self.parity_fragments,
-                    desc,
-                })
-                .map_err(Error::from_error_code)?;
+    fn new_coder(&self, engine: Engine) -> ErasureCoder {
+        ErasureCoder {
+            data_fragments: self.data_fragments,
+            parity_fragments: self.parity_fragments,
+            backend: self.backend,
+            checksum: self.checksum,
+            engine,
+        }
+    }
+}
 
-            // `SIGSEGV` may be raised if encodings are executed (in parallel) immediately after creation.
-            // To prevent it, sleeps the current thread for a little while.
-            std::thread::sleep(Duration::from_millis(10));
-            Ok(coder)
-        })
+/// Options for [`ErasureCoder::decode_with`].
+///
+/// [`ErasureCoder::decode_with`]: ./struct.ErasureCoder.html#method.decode_with
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    force_metadata_checks: bool,
+    verify_checksum: bool,
+}
+impl DecodeOptions {
+    /// Makes a new `DecodeOptions` with the default settings.
+    ///
+    /// All the checks are disabled by default, which is equivalent to [`ErasureCoder::decode`].
+    ///
+    /// [`ErasureCoder::decode`]: ./struct.ErasureCoder.html#method.decode
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes [openstack/liberasurecode] check the header of every fragment before decoding.
+    ///
+    /// Fragments with invalid headers are not counted as available fragments.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn force_metadata_checks(&mut self, enabled: bool) -> &mut Self {
+        self.force_metadata_checks = enabled;
+        self
+    }
+
+    /// Verifies the checksum of every fragment before decoding.
+    ///
+    /// Fragments must have the checksum type configured by [`Builder::checksum`].
+    /// Note that [openstack/liberasurecode] does not compute MD5 digests,
+    /// so decoding fails with `Error::EcMethodNotImplemented` if `Checksum::Md5` is used.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`Builder::checksum`]: ./struct.Builder.html#method.checksum
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn verify_checksum(&mut self, enabled: bool) -> &mut Self {
+        self.verify_checksum = enabled;
+        self
+    }
+}
+
+/// The result of [`ErasureCoder::decode_excluding_corrupted`].
+///
+/// [`ErasureCoder::decode_excluding_corrupted`]:
+/// ./struct.ErasureCoder.html#method.decode_excluding_corrupted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveredData {
+    data: Vec<u8>,
+    corrupted_fragments: Vec<CorruptedFragment>,
+}
+impl RecoveredData {
+    /// Returns a reference to the decoded data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Takes ownership of the decoded data.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Returns the fragments discarded as corrupted (in the order of the given slice).
+    pub fn corrupted_fragments(&self) -> &[CorruptedFragment] {
+        &self.corrupted_fragments
     }
 }
 
+/// A fragment discarded by [`ErasureCoder::decode_excluding_corrupted`].
+///
+/// [`ErasureCoder::decode_excluding_corrupted`]:
+/// ./struct.ErasureCoder.html#method.decode_excluding_corrupted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CorruptedFragment {
+    /// The fragment index recorded in the header.
+    ///
+    /// This is used if the header can be parsed (i.e., [`FragmentMetadata::parse`] succeeds).
+    ///
+    /// [`FragmentMetadata::parse`]: ./struct.FragmentMetadata.html#method.parse
+    Index(usize),
+
+    /// The position of the fragment in the given slice.
+    ///
+    /// This is used if the header is too broken to know the index.
+    Position(usize),
+}
+
 /// Erasure coder.
 ///
+/// # Thread Safety
+///
+/// `ErasureCoder` is `Send` and `Sync`, so a coder can be shared by multiple threads
+/// (e.g., via `Arc`) without any external synchronization.
+///
+/// This is sound because [openstack/liberasurecode] looks descriptors up under
+/// its internal reader-writer lock, and the backends only read the coding matrices and tables
+/// prepared at creation time while encoding, decoding or reconstructing.
+/// All the other states (e.g., fragment buffers and decoding matrices) are allocated per call.
+///
+/// Coders can also be created and dropped concurrently.
+/// The states shared by all coders (i.e., the descriptor registry of [openstack/liberasurecode]
+/// and the GF tables of the backends) are initialized only once and never released while
+/// the process is running.
+///
+/// Note that this does not hold if the crate is linked against a system-installed
+/// [openstack/liberasurecode] (i.e., `LIBERASURECODE_SYSTEM=1`), since it is not patched by this crate:
+/// dropping a `Backend::JerasureRsVand` or `Backend::JerasureRsCauchy` coder releases
+/// the GF fields used by the other coders. In that case, all the calls to
+/// [openstack/liberasurecode] (by any coder) are serialized by a global lock,
+/// so coders are still safe to use concurrently, but do not run in parallel.
+///
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+///
 /// # Examples
 ///
 /// ```
@@ -192,7 +553,7 @@ impl Builder {
 /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// let data_fragments = NonZeroUsize::new(4).ok_or("too few fragments")?;
 /// let parity_fragments = NonZeroUsize::new(2).ok_or("too few fragments")?;
-/// let mut coder = ErasureCoder::new(data_fragments, parity_fragments)?;
+/// let coder = ErasureCoder::new(data_fragments, parity_fragments)?;
 /// let data = vec![0, 1, 2, 3];
 /// let encoded = coder.encode(&data)?;
 ///
@@ -208,7 +569,9 @@ impl Builder {
 pub struct ErasureCoder {
     data_fragments: NonZeroUsize,
     parity_fragments: NonZeroUsize,
-    desc: c_api::Desc,
+    backend: Backend,
+    checksum: Checksum,
+    engine: Engine,
 }
 impl ErasureCoder {
     /// Makes a new `ErasureCoder` instance with the default settings.
@@ -218,6 +581,18 @@ impl ErasureCoder {
         Builder::new(data_fragments, parity_fragments).finish()
     }
 
+    /// Returns a coder configured by the given builder, which is shared in the process.
+    ///
+    /// Coders with the same configuration share a single [openstack/liberasurecode] descriptor,
+    /// and it is destroyed when the last handle is dropped.
+    /// See [`CoderCache`] for more details.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    /// [`CoderCache`]: ./struct.CoderCache.html
+    pub fn shared(builder: &Builder) -> Result<Arc<Self>> {
+        CoderCache::global().get(builder)
+    }
+
     /// Returns the number of data fragments specified to the coder.
     pub fn data_fragments(&self) -> NonZeroUsize {
         self.data_fragments
@@ -235,42 +610,339 @@ impl ErasureCoder {
         }
     }
 
-    /// Encodes the given data to data and parity fragments.
-    pub fn encode(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        let (encoded_data, encoded_parity, fragment_len) =
-            c_api::encode(self.desc, data).map_err(Error::from_error_code)?;
+    /// Returns the size of each fragment (including its header) generated by
+    /// encoding data of the given length.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::InvalidParams` if `data_len` is too large.
+    pub fn fragment_size(&self, data_len: usize) -> Result<usize> {
+        if data_len > c_int::MAX as usize {
+            return Err(Error::InvalidParams);
+        }
+        let size = match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => c_api::get_fragment_size(desc, data_len as c_int)
+                .map_err(Error::from_error_code)? as usize,
+            Engine::PureRust(ref rs) => rs.fragment_payload_size(data_len),
+        };
+        Ok(FragmentMetadata::HEADER_SIZE + size)
+    }
+
+    /// Returns the size of the given length of data after padded to align it for encoding.
+    ///
+    /// The result is the sum of the payload sizes of the data fragments.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::InvalidParams` if `data_len` is too large.
+    pub fn aligned_data_size(&self, data_len: usize) -> Result<usize> {
+        if data_len > c_int::MAX as usize {
+            return Err(Error::InvalidParams);
+        }
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => {
+                let size = c_api::get_aligned_data_size(desc, data_len as u64)
+                    .map_err(Error::from_error_code)?;
+                Ok(size as usize)
+            }
+            Engine::PureRust(ref rs) => Ok(rs.aligned_data_size(data_len)),
+        }
+    }
+
+    /// Returns the minimum size of data to which encoding does not add padding
+    /// other than the alignment.
+    ///
+    /// Any data shorter than this are padded to this size before encoding.
+    pub fn minimum_encode_size(&self) -> Result<usize> {
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => {
+                let size = c_api::get_minimum_encode_size(desc).map_err(Error::from_error_code)?;
+                Ok(size as usize)
+            }
+            Engine::PureRust(ref rs) => Ok(rs.minimum_encode_size()),
+        }
+    }
+
+    /// Parses the metadata stored in the header of the given fragment.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::BadHeader` if the fragment does not start with
+    /// a valid header or is shorter than the payload size recorded in the header.
+    pub fn fragment_metadata(&self, fragment: &[u8]) -> Result<FragmentMetadata> {
+        FragmentMetadata::parse(fragment)
+    }
+
+    /// Returns `true` if the given fragment is well-formed and can be processed by the coder.
+    ///
+    /// Use [`verify_stripe`] to know why a fragment is considered invalid.
+    ///
+    /// [`verify_stripe`]: #method.verify_stripe
+    pub fn is_valid_fragment(&self, fragment: &[u8]) -> bool {
+        verify::diagnose_fragment(self, fragment).is_none()
+    }
+
+    /// Verifies the given fragments which belong to the same stripe.
+    ///
+    /// The resulting report tells which fragments are invalid and why.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::InvalidParams` if `fragments` is empty.
+    /// The errors of [openstack/liberasurecode] other than those about the fragments
+    /// (e.g., an invalid descriptor) are returned as they are.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn verify_stripe<T: AsRef<[u8]>>(&self, fragments: &[T]) -> Result<StripeReport> {
+        let fragments = fragments.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        verify::verify_stripe(self, &fragments)
+    }
 
-        let mut fragments = Vec::with_capacity(self.fragments().get());
+    /// Encodes the given data to data and parity fragments.
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(_) => {
+                let fragments = self.encode_borrowed(data)?;
+                Ok(fragments.iter().map(Vec::from).collect())
+            }
+            Engine::PureRust(ref rs) => Ok(rs.encode(data)),
+        }
+    }
 
-        let data_fragments =
-            unsafe { slice::from_raw_parts(encoded_data, self.data_fragments.get()) };
-        fragments.extend((0..self.data_fragments.get()).map(|i| {
-            Vec::from(unsafe { slice::from_raw_parts(data_fragments[i], fragment_len as usize) })
-        }));
+    /// Encodes the given data to data and parity fragments without copying them.
+    ///
+    /// The resulting fragments refer to the buffers allocated by [openstack/liberasurecode]
+    /// which are released when the returned value is dropped.
+    /// (`Backend::PureRustRs` encodes data into buffers allocated by Rust.)
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn encode_borrowed(&self, data: &[u8]) -> Result<EncodedFragments<'_>> {
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => {
+                let (encoded_data, encoded_parity, fragment_len) =
+                    c_api::encode(desc, data).map_err(Error::from_error_code)?;
+                Ok(unsafe {
+                    EncodedFragments::new(self, desc, encoded_data, encoded_parity, fragment_len)
+                })
+            }
+            Engine::PureRust(ref rs) => Ok(EncodedFragments::from_vec(self, rs.encode(data))),
+        }
+    }
 
-        let parity_fragments =
-            unsafe { slice::from_raw_parts(encoded_parity, self.parity_fragments.get()) };
-        fragments.extend((0..self.parity_fragments.get()).map(|i| {
-            Vec::from(unsafe { slice::from_raw_parts(parity_fragments[i], fragment_len as usize) })
-        }));
+    /// Encodes the given data to data and parity fragments, and writes them to `fragments`.
+    ///
+    /// `fragments` must consist of `self.fragments()` buffers and each of them must be
+    /// at least [`fragment_size(data.len())`] bytes long.
+    /// Unlike [`encode`], this does not allocate memory for the resulting fragments:
+    /// `Backend::PureRustRs` writes the headers and the payloads directly into `fragments`.
+    /// Note that [openstack/liberasurecode] always encodes data into internally allocated
+    /// buffers, so the other backends copy the fragments from there to `fragments`.
+    ///
+    /// Returns the size of each fragment (i.e., the number of bytes written to each buffer).
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::InvalidParams` if the number or the sizes of
+    /// the buffers are insufficient.
+    ///
+    /// [`fragment_size(data.len())`]: #method.fragment_size
+    /// [`encode`]: #method.encode
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn encode_into(&self, data: &[u8], fragments: &mut [&mut [u8]]) -> Result<usize> {
+        if fragments.len() != self.fragments().get() {
+            return Err(Error::InvalidParams);
+        }
+        let fragment_size = self.fragment_size(data.len())?;
+        if fragments.iter().any(|f| f.len() < fragment_size) {
+            return Err(Error::InvalidParams);
+        }
 
-        c_api::encode_cleanup(self.desc, encoded_data, encoded_parity)
-            .map_err(Error::from_error_code)?;
-        Ok(fragments)
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(_) => {
+                let encoded = self.encode_borrowed(data)?;
+                let fragment_len = encoded.fragment_len();
+                if fragment_len > fragment_size {
+                    // Never happens unless `fragment_size` method is broken.
+                    return Err(Error::InvalidParams);
+                }
+                for (buf, fragment) in fragments.iter_mut().zip(encoded.iter()) {
+                    buf[..fragment_len].copy_from_slice(fragment);
+                }
+                Ok(fragment_len)
+            }
+            Engine::PureRust(ref rs) => Ok(rs.encode_into(data, fragments)),
+        }
     }
 
     /// Decodes the original data from the given fragments.
-    pub fn decode<T: AsRef<[u8]>>(&mut self, fragments: &[T]) -> Result<Vec<u8>> {
+    ///
+    /// This is equivalent to `self.decode_with(fragments, &DecodeOptions::new())`.
+    pub fn decode<T: AsRef<[u8]>>(&self, fragments: &[T]) -> Result<Vec<u8>> {
+        self.decode_with(fragments, &DecodeOptions::new())
+    }
+
+    /// Decodes the original data from the given fragments with the given options.
+    ///
+    /// # Errors
+    ///
+    /// The fragments are validated before being passed to [openstack/liberasurecode].
+    /// This function will return `Error::InvalidFragment` with the position of the first
+    /// malformed fragment and the reason if the lengths of the fragments differ,
+    /// or a fragment is shorter than the header, has a bad magic number,
+    /// its header is inconsistent with its length, its index is out of range,
+    /// or its original data size is too large.
+    ///
+    /// If `options.verify_checksum(true)` is specified, this function will also return
+    /// `Error::BadChecksum` if the checksum of a fragment does not match,
+    /// and `Error::EcMethodNotImplemented` if the coder uses `Checksum::Md5`
+    /// (which cannot be verified).
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn decode_with<T: AsRef<[u8]>>(
+        &self,
+        fragments: &[T],
+        options: &DecodeOptions,
+    ) -> Result<Vec<u8>> {
+        self.decode_raw(fragments, options)
+            .map(|data| data.to_vec())
+    }
+
+    fn decode_raw<T: AsRef<[u8]>>(
+        &self,
+        fragments: &[T],
+        options: &DecodeOptions,
+    ) -> Result<DecodedData<'_>> {
         if fragments.is_empty() {
             return Err(Error::InsufficientFragments);
         }
         let data_fragments = &fragments.iter().map(AsRef::as_ref).collect::<Vec<_>>()[..];
+        metadata::check_fragments(
+            data_fragments,
+            self.data_fragments.get(),
+            self.fragments().get(),
+        )?;
+
+        if options.verify_checksum {
+            for fragment in data_fragments {
+                self.verify_checksum(fragment)?;
+            }
+        }
+
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => {
+                let (data, data_len) =
+                    c_api::decode(desc, data_fragments, options.force_metadata_checks)
+                        .map_err(Error::from_error_code)?;
+                Ok(unsafe { DecodedData::new(self, desc, data, data_len) })
+            }
+            Engine::PureRust(ref rs) => {
+                let data = if options.force_metadata_checks {
+                    let valid_fragments = data_fragments
+                        .iter()
+                        .cloned()
+                        .filter(|fragment| self.is_valid_fragment(fragment))
+                        .collect::<Vec<_>>();
+                    if valid_fragments.is_empty() {
+                        return Err(Error::InsufficientFragments);
+                    }
+                    rs.decode(&valid_fragments)?
+                } else {
+                    rs.decode(data_fragments)?
+                };
+                Ok(DecodedData::from_vec(self, data))
+            }
+        }
+    }
+
+    /// Decodes the original data from the given fragments without copying it.
+    ///
+    /// The resulting data refers to the buffer allocated by [openstack/liberasurecode]
+    /// which is released when the returned value is dropped.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn decode_borrowed<T: AsRef<[u8]>>(&self, fragments: &[T]) -> Result<DecodedData<'_>> {
+        self.decode_raw(fragments, &DecodeOptions::new())
+    }
+
+    /// Decodes the original data from the given fragments, and writes it to `buf`.
+    ///
+    /// The previous contents of `buf` are discarded,
+    /// but the allocated memory is reused if its capacity is sufficient.
+    pub fn decode_into<T: AsRef<[u8]>>(&self, fragments: &[T], buf: &mut Vec<u8>) -> Result<()> {
+        let data = self.decode_borrowed(fragments)?;
+        buf.clear();
+        buf.extend_from_slice(&data);
+        Ok(())
+    }
+
+    /// Decodes the original data from the given fragments excluding corrupted ones.
+    ///
+    /// A fragment is regarded as corrupted if it is reported as invalid by [`verify_stripe`]
+    /// (e.g., its header is broken, or its length or original data size differs from
+    /// those of the majority of the fragments), or if its checksum does not match
+    /// (see [`DecodeOptions::verify_checksum`]).
+    /// The remaining fragments are used for decoding.
+    /// Note that corrupted payloads can be detected only if `Checksum::Crc32` is used.
+    ///
+    /// The discarded fragments are identified by the indices recorded in their headers
+    /// (as [`StripeReport`] is), or by their positions in `fragments` if their headers
+    /// cannot be parsed (see [`CorruptedFragment`]).
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::InsufficientFragments` if fewer than
+    /// `data_fragments` fragments remain after excluding the corrupted ones,
+    /// and `Error::EcMethodNotImplemented` if the coder uses `Checksum::Md5`
+    /// (which cannot be verified).
+    ///
+    /// [`verify_stripe`]: #method.verify_stripe
+    /// [`DecodeOptions::verify_checksum`]: ./struct.DecodeOptions.html#method.verify_checksum
+    /// [`StripeReport`]: ./struct.StripeReport.html
+    /// [`CorruptedFragment`]: ./enum.CorruptedFragment.html
+    pub fn decode_excluding_corrupted<T: AsRef<[u8]>>(
+        &self,
+        fragments: &[T],
+    ) -> Result<RecoveredData> {
+        if self.checksum == Checksum::Md5 {
+            return Err(Error::EcMethodNotImplemented);
+        }
+        let report = if fragments.is_empty() {
+            StripeReport::default()
+        } else {
+            self.verify_stripe(fragments)?
+        };
+
+        let mut corrupted_fragments = Vec::new();
+        let mut intact_fragments = Vec::new();
+        for (i, fragment) in fragments.iter().map(AsRef::as_ref).enumerate() {
+            let is_invalid = report.invalid_fragments().iter().any(|&(j, _)| j == i);
+            if !is_invalid && self.verify_checksum(fragment).is_ok() {
+                intact_fragments.push(fragment);
+            } else {
+                corrupted_fragments.push(match FragmentMetadata::parse(fragment) {
+                    Ok(metadata) => CorruptedFragment::Index(metadata.index()),
+                    Err(_) => CorruptedFragment::Position(i),
+                });
+            }
+        }
+        if intact_fragments.len() < self.data_fragments.get() {
+            return Err(Error::InsufficientFragments);
+        }
 
-        let (data, data_len) =
-            c_api::decode(self.desc, data_fragments, false).map_err(Error::from_error_code)?;
-        let buf = Vec::from(unsafe { slice::from_raw_parts(data, data_len as usize) });
-        c_api::decode_cleanup(self.desc, data).map_err(Error::from_error_code)?;
-        Ok(buf)
+        let mut options = DecodeOptions::new();
+        options.force_metadata_checks(true);
+        let data = self.decode_with(&intact_fragments, &options)?;
+        Ok(RecoveredData {
+            data,
+            corrupted_fragments,
+        })
     }
 
     /// Reconstructs the fragment specified by the given index from other available fragments.
@@ -279,7 +951,11 @@ impl ErasureCoder {
     ///
     /// This function will return `Error::InvalidParams` if the given index is bigger or equal
     /// than the total number of parity_fragments and data_fragments.
-    pub fn reconstruct<T, F>(&mut self, index: usize, available_fragments: T) -> Result<Vec<u8>>
+    ///
+    /// The available fragments are validated in the same way as [`decode_with`].
+    ///
+    /// [`decode_with`]: #method.decode_with
+    pub fn reconstruct<T, F>(&self, index: usize, available_fragments: T) -> Result<Vec<u8>>
     where
         T: Iterator<Item = F>,
         F: AsRef<[u8]>,
@@ -290,50 +966,218 @@ impl ErasureCoder {
 
         let fragments = available_fragments.collect::<Vec<_>>();
         let fragments = fragments.iter().map(AsRef::as_ref).collect::<Vec<_>>();
-        c_api::reconstruct_fragment(self.desc, &fragments[..], index)
-            .map_err(Error::from_error_code)
-    }
-}
-impl Drop for ErasureCoder {
-    fn drop(&mut self) {
-        let _ = c_api::instance_destroy(self.desc);
+        if fragments.is_empty() {
+            return Err(Error::InsufficientFragments);
+        }
+        metadata::check_fragments(
+            &fragments,
+            self.data_fragments.get(),
+            self.fragments().get(),
+        )?;
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => c_api::reconstruct_fragment(desc, &fragments[..], index)
+                .map_err(Error::from_error_code),
+            Engine::PureRust(ref rs) => rs.reconstruct(&fragments, index),
+        }
     }
-}
-
-fn with_global_lock<F, T>(f: F) -> T
-where
-    F: FnOnce() -> T,
-{
-    use std::sync::{Mutex, Once};
-
-    static mut MUTEX: Option<Mutex<()>> = None;
-    static INIT: Once = Once::new();
-    INIT.call_once(|| unsafe {
-        MUTEX = Some(Mutex::default());
-    });
-
-    let _guard = unsafe {
-        MUTEX
-            .as_ref()
-            .expect("Never fails")
-            .lock()
-            .expect("Poisoned global lock")
-    };
-    f()
-}
-
-#[cfg(test)]
-mod tests {
-    use std::num::NonZeroUsize;
-
-    use super::*;
-    use crate::result::Error;
 
-    #[test]
-    fn it_works() {
-        let mut coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
-        let data = vec![0, 1, 2, 3];
-        let encoded = coder.encode(&data).unwrap();
+    /// Reconstructs the fragments specified by the given indices from other available fragments.
+    ///
+    /// The available fragments are validated and decoded only once for all the indices:
+    /// `Backend::PureRustRs` derives the requested fragments from the decoded blocks,
+    /// and the other MDS backends (i.e., Reed-Solomon codes) re-encode the decoded data
+    /// if multiple fragments are requested.
+    /// `Backend::FlatXorHd` (and `Backend::Null`) reconstruct each fragment separately
+    /// in the same way as [`reconstruct`], since the XOR codes can reconstruct a fragment
+    /// from fewer fragments than the data fragments, which may be insufficient for decoding.
+    ///
+    /// The resulting vector holds pairs of an index and the reconstructed fragment
+    /// in the order of `indices`.
+    ///
+    /// [`reconstruct`]: #method.reconstruct
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::InvalidParams` if any of the given indices is bigger or
+    /// equal than the total number of parity_fragments and data_fragments, or is duplicated.
+    ///
+    /// The available fragments are validated in the same way as [`decode_with`].
+    ///
+    /// [`decode_with`]: #method.decode_with
+    pub fn reconstruct_many<T, F>(
+        &self,
+        indices: &[usize],
+        available_fragments: T,
+    ) -> Result<Vec<(usize, Vec<u8>)>>
+    where
+        T: Iterator<Item = F>,
+        F: AsRef<[u8]>,
+    {
+        for (i, &index) in indices.iter().enumerate() {
+            if index >= self.fragments().get() || indices[..i].contains(&index) {
+                return Err(Error::InvalidParams);
+            }
+        }
+        if indices.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let fragments = available_fragments.collect::<Vec<_>>();
+        let fragments = fragments.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        if fragments.is_empty() {
+            return Err(Error::InsufficientFragments);
+        }
+        metadata::check_fragments(
+            &fragments,
+            self.data_fragments.get(),
+            self.fragments().get(),
+        )?;
+        let reconstructed = match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(_) if self.backend.is_mds() && indices.len() > 1 => {
+                let data = self.decode_borrowed(&fragments)?;
+                let encoded = self.encode_borrowed(&data)?;
+                indices
+                    .iter()
+                    .map(|&index| encoded[index].to_vec())
+                    .collect()
+            }
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => indices
+                .iter()
+                .map(|&index| {
+                    c_api::reconstruct_fragment(desc, &fragments[..], index)
+                        .map_err(Error::from_error_code)
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Engine::PureRust(ref rs) => rs.reconstruct_many(&fragments, indices)?,
+        };
+        Ok(indices.iter().copied().zip(reconstructed).collect())
+    }
+
+    /// Returns the indices of the fragments that need to be read to reconstruct
+    /// the fragments specified by `to_reconstruct`.
+    ///
+    /// The fragments specified by `to_exclude` (e.g., those located on unavailable nodes)
+    /// are never included in the result.
+    ///
+    /// For some backends (e.g., `Backend::FlatXorHd`), the resulting set may be smaller than
+    /// the number of data fragments.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::InvalidParams` if any of the given indices is bigger or
+    /// equal than the total number of parity_fragments and data_fragments.
+    ///
+    /// If the remaining fragments are insufficient for the reconstruction,
+    /// `Error::InsufficientFragments` will be returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use liberasurecode::ErasureCoder;
+    /// use std::num::NonZeroUsize;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data_fragments = NonZeroUsize::new(4).ok_or("too few fragments")?;
+    /// let parity_fragments = NonZeroUsize::new(2).ok_or("too few fragments")?;
+    /// let coder = ErasureCoder::new(data_fragments, parity_fragments)?;
+    ///
+    /// let needed = coder.fragments_needed(&[0], &[1])?;
+    /// assert_eq!(needed.len(), 4);
+    /// assert!(!needed.contains(&0));
+    /// assert!(!needed.contains(&1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fragments_needed(
+        &self,
+        to_reconstruct: &[usize],
+        to_exclude: &[usize],
+    ) -> Result<Vec<usize>> {
+        let fragments = self.fragments().get();
+        if to_reconstruct
+            .iter()
+            .chain(to_exclude.iter())
+            .any(|&index| index >= fragments)
+        {
+            return Err(Error::InvalidParams);
+        }
+
+        match self.engine {
+            #[cfg(feature = "native")]
+            Engine::Native(desc) => {
+                c_api::fragments_needed(desc, to_reconstruct, to_exclude, fragments)
+                    .map_err(fragments_needed_error)
+            }
+            Engine::PureRust(ref rs) => rs.fragments_needed(to_reconstruct, to_exclude),
+        }
+    }
+
+    fn verify_checksum(&self, fragment: &[u8]) -> Result<()> {
+        if self.checksum == Checksum::Md5 {
+            // The MD5 digests are never computed (i.e., left zero-filled) by liberasurecode.
+            return Err(Error::EcMethodNotImplemented);
+        }
+        let metadata = FragmentMetadata::parse(fragment)?;
+        if metadata.checksum_type() != self.checksum || metadata.checksum_mismatch() {
+            return Err(Error::BadChecksum);
+        }
+        Ok(())
+    }
+}
+/// Converts an error code of `liberasurecode_fragments_needed` into an `Error`.
+///
+/// Some backends (e.g., Jerasure) report insufficient fragments by `-1` instead of `-EINSUFFFRAGS`,
+/// so both of them are mapped to `Error::InsufficientFragments`.
+/// The other codes (e.g., for invalid descriptors or allocation failures) are kept as they are.
+#[cfg(feature = "native")]
+fn fragments_needed_error(code: c_api::ErrorCode) -> Error {
+    const INSUFFICIENT_FRAGMENTS_BY_BACKEND: c_api::ErrorCode = 1;
+    match code {
+        INSUFFICIENT_FRAGMENTS_BY_BACKEND => Error::InsufficientFragments,
+        code => Error::from_error_code(code),
+    }
+}
+
+#[cfg(feature = "native")]
+impl Drop for ErasureCoder {
+    fn drop(&mut self) {
+        if let Engine::Native(desc) = self.engine {
+            let _ = c_api::instance_destroy(desc);
+        }
+    }
+}
+
+/// The implementation of the backend of an `ErasureCoder`.
+#[derive(Debug)]
+enum Engine {
+    /// A descriptor of [openstack/liberasurecode].
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    #[cfg(feature = "native")]
+    Native(c_api::Desc),
+
+    /// `Backend::PureRustRs`.
+    PureRust(pure_rust::ReedSolomon),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::result::Error;
+
+    #[test]
+    fn it_works() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let data = vec![0, 1, 2, 3];
+        let encoded = coder.encode(&data).unwrap();
 
         assert_eq!(Ok(&data), coder.decode(&encoded[0..]).as_ref());
         assert_eq!(Ok(&data), coder.decode(&encoded[1..]).as_ref());
@@ -346,7 +1190,7 @@ mod tests {
 
     #[test]
     fn reconstruct_works() {
-        let mut coder = ErasureCoder::new(non_zero(4), non_zero(4)).unwrap();
+        let coder = ErasureCoder::new(non_zero(4), non_zero(4)).unwrap();
         let data = vec![0, 1, 2, 3];
         let encoded = coder.encode(&data).unwrap();
 
@@ -371,7 +1215,7 @@ mod tests {
         let k = 6;
         let m = 3;
         let len = 0xc0de;
-        let mut coder = ErasureCoder::new(non_zero(k), non_zero(m)).unwrap();
+        let coder = ErasureCoder::new(non_zero(k), non_zero(m)).unwrap();
         let mut data = vec![0; len];
         let mut seed: u32 = 0xdeadbeef;
         for item in data.iter_mut() {
@@ -404,9 +1248,423 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "isa-l")]
+    fn isa_l_works() -> Result<()> {
+        for &backend in &[Backend::IsaLRsVand, Backend::IsaLRsCauchy] {
+            // A system-installed liberasurecode loads ISA-L only if it is installed.
+            if cfg!(liberasurecode_system) && !backend.is_available() {
+                continue;
+            }
+            assert!(backend.is_available(), "b={:?}", backend);
+            for &(k, m) in &[(4, 2), (6, 3), (10, 4)] {
+                check_all_combinations(backend, k, m, Checksum::None)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn pure_rust_rs_works() -> Result<()> {
+        let backend = Backend::PureRustRs;
+        assert!(backend.is_available());
+        for &(k, m) in &[(2, 1), (4, 2), (6, 3), (10, 4), (3, 6)] {
+            check_all_combinations(backend, k, m, Checksum::None)?;
+        }
+        check_all_combinations(backend, 4, 2, Checksum::Crc32)?;
+
+        // The largest parameters.
+        let coder = Builder::new(non_zero(200), non_zero(56))
+            .backend(backend)
+            .finish()?;
+        let data = (0..10_000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+        let encoded = coder.encode(&data)?;
+        assert_eq!(coder.decode(&encoded[56..])?, data);
+        assert_eq!(coder.reconstruct(0, encoded[56..].iter())?, encoded[0]);
+        assert_eq!(
+            coder.decode(&encoded[57..]),
+            Err(Error::InsufficientFragments)
+        );
+        assert_eq!(
+            Builder::new(non_zero(200), non_zero(57))
+                .backend(backend)
+                .finish()
+                .err(),
+            Some(Error::InvalidParams)
+        );
+
+        // The fragments share the header layout with the other backends.
+        let coder = Builder::new(non_zero(4), non_zero(2))
+            .backend(backend)
+            .checksum(Checksum::Crc32)
+            .finish()?;
+        let encoded = coder.encode(&data)?;
+        assert_eq!(encoded[0].len(), coder.fragment_size(data.len())?);
+        assert_eq!(
+            encoded[0].len() - FragmentMetadata::HEADER_SIZE,
+            coder.aligned_data_size(data.len())? / 4
+        );
+        assert!(coder.verify_stripe(&encoded)?.is_valid());
+
+        let mut corrupted = encoded.clone();
+        corrupted[1][FragmentMetadata::HEADER_SIZE] ^= 1;
+        let recovered = coder.decode_excluding_corrupted(&corrupted)?;
+        assert_eq!(recovered.data(), &data[..]);
+        assert_eq!(
+            recovered.corrupted_fragments(),
+            &[CorruptedFragment::Index(1)]
+        );
+
+        #[cfg(feature = "native")]
+        {
+            let other_coder = Builder::new(non_zero(4), non_zero(2))
+                .backend(Backend::LiberasurecodeRsVand)
+                .finish()?;
+            assert_eq!(
+                other_coder
+                    .verify_stripe(&encoded[..1])?
+                    .invalid_fragments(),
+                &[(0, FragmentError::BackendMismatch)]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn crc32_works() {
+        // The values computed by `crc32(0, buf, len)` of liberasurecode 1.5.0
+        // (which differ from the standard CRC-32 if the sign bit is set).
+        assert_eq!(crc32::crc32(b""), 0);
+        assert_eq!(crc32::crc32(b"123456789"), 0x206a_f85b);
+        assert_eq!(crc32::crc32(&(0..=255).collect::<Vec<u8>>()), 0x5f7c_dea4);
+        assert_eq!(crc32::crc32(&[0xff; 16]), 0);
+    }
+
+    #[test]
+    fn gf_works() {
+        for a in 1..=255 {
+            assert_eq!(gf::mul(a, gf::inv(a)), 1, "a={}", a);
+            assert_eq!(gf::mul(a, 1), a);
+            assert_eq!(gf::mul(a, 0), 0);
+            for b in 1..=255 {
+                assert_eq!(gf::mul(a, b), gf::mul(b, a));
+                assert_ne!(gf::mul(a, b), 0);
+            }
+        }
+
+        let src = (0..=255).collect::<Vec<u8>>();
+        for &c in &[0, 1, 2, 0x8e, 0xff] {
+            let mut dst = vec![0x5a; src.len()];
+            gf::mul_add_slice(c, &src, &mut dst);
+            for (x, y) in src.iter().zip(dst.iter()) {
+                assert_eq!(*y, 0x5a ^ gf::mul(c, *x));
+            }
+        }
+
+        let mut matrix = vec![vec![1, 1], vec![1, 1]];
+        assert!(!gf::invert_matrix(&mut matrix));
+    }
+
+    #[test]
+    fn gf_kernels_work() {
+        let kernels = gf::Kernel::available();
+        assert_eq!(kernels[0], gf::Kernel::Scalar);
+        assert_eq!(gf::Kernel::best(), *kernels.last().unwrap());
+
+        // Covers the lengths not aligned to the SIMD registers (i.e., the scalar tails).
+        for len in [0, 1, 15, 16, 17, 31, 32, 33, 100, 4096 + 7] {
+            let src = (0..len).map(|i| (i * 7 + 3) as u8).collect::<Vec<_>>();
+            for c in [0, 1, 2, 0x0f, 0x10, 0x8e, 0xff] {
+                let mut expected = vec![0xa5; len];
+                gf::Kernel::Scalar.mul_add_slice(c, &src, &mut expected);
+                for &kernel in &kernels {
+                    let mut dst = vec![0xa5; len];
+                    kernel.mul_add_slice(c, &src, &mut dst);
+                    assert_eq!(dst, expected, "kernel={:?}, len={}, c={}", kernel, len, c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reconstruct_many_works() {
+        for backend in available_backends() {
+            if is_null(backend) {
+                // The null backend does not generate meaningful parity fragments.
+                continue;
+            }
+            for (data_fragments, parity_fragments) in params(backend) {
+                let coder = Builder::new(data_fragments, parity_fragments)
+                    .backend(backend)
+                    .finish()
+                    .unwrap();
+
+                let data = (0..0x1234).map(|i| i as u8).collect::<Vec<_>>();
+                let encoded = coder.encode(&data).unwrap();
+                let lost = fault_tolerance(backend, parity_fragments.get());
+                for start in 0..=coder.fragments().get() - lost {
+                    let indices = (start..start + lost).rev().collect::<Vec<_>>();
+                    let available = || {
+                        encoded
+                            .iter()
+                            .enumerate()
+                            .filter(|&(index, _)| !indices.contains(&index))
+                            .map(|(_, f)| f)
+                    };
+                    let expected = indices
+                        .iter()
+                        .map(|&i| (i, coder.reconstruct(i, available()).unwrap()))
+                        .collect::<Vec<_>>();
+                    assert_eq!(
+                        coder.reconstruct_many(&indices, available()),
+                        Ok(expected.clone()),
+                        "k={}, m={}, b={:?}, indices={:?}",
+                        data_fragments,
+                        parity_fragments,
+                        backend,
+                        indices
+                    );
+
+                    // Only the needed fragments (fewer than the data fragments for `FlatXorHd`)
+                    let needed = coder.fragments_needed(&indices, &[]).unwrap();
+                    let needed_fragments = needed.iter().map(|&i| &encoded[i]);
+                    assert_eq!(
+                        coder.reconstruct_many(&indices, needed_fragments),
+                        Ok(expected),
+                        "k={}, m={}, b={:?}, indices={:?}, needed={:?}",
+                        data_fragments,
+                        parity_fragments,
+                        backend,
+                        indices,
+                        needed
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reconstruct_many_fails() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let encoded = coder.encode(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(coder.reconstruct_many(&[], encoded.iter()), Ok(Vec::new()));
+        assert_eq!(
+            coder.reconstruct_many(&[0, 6], encoded[1..].iter()),
+            Err(Error::InvalidParams)
+        );
+        assert_eq!(
+            coder.reconstruct_many(&[0, 1, 0], encoded[2..].iter()),
+            Err(Error::InvalidParams)
+        );
+        assert_eq!(
+            coder.reconstruct_many(&[0, 1, 2], encoded[3..].iter()),
+            Err(Error::InsufficientFragments)
+        );
+    }
+
+    #[test]
+    fn fragments_needed_works() {
+        for backend in available_backends() {
+            if is_null(backend) {
+                // The null backend does not generate meaningful parity fragments.
+                continue;
+            }
+            for (data_fragments, parity_fragments) in params(backend) {
+                let coder = Builder::new(data_fragments, parity_fragments)
+                    .backend(backend)
+                    .finish()
+                    .unwrap();
+                let k = data_fragments.get();
+                let n = coder.fragments().get();
+                let lost = fault_tolerance(backend, parity_fragments.get());
+
+                let data = (0..0x1234).map(|i| i as u8).collect::<Vec<_>>();
+                let encoded = coder.encode(&data).unwrap();
+                for i in 0..n {
+                    let excluded = (0..lost - 1).map(|j| (i + j + 1) % n).collect::<Vec<_>>();
+                    let needed = coder.fragments_needed(&[i], &excluded).unwrap();
+                    assert!(needed.len() <= k);
+                    assert!(needed.iter().all(|j| *j < n && *j != i));
+                    assert!(needed.iter().all(|j| !excluded.contains(j)));
+
+                    // The reconstruction succeeds only with the needed fragments.
+                    let available = needed.iter().map(|&j| &encoded[j]);
+                    assert_eq!(
+                        coder.reconstruct(i, available),
+                        Ok(encoded[i].clone()),
+                        "k={}, m={}, b={:?}, i={}, needed={:?}",
+                        data_fragments,
+                        parity_fragments,
+                        backend,
+                        i,
+                        needed
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn fragments_needed_errors_are_passed_through() {
+        let insufficient = Error::InsufficientFragments.as_error_code();
+        assert_eq!(fragments_needed_error(1), Error::InsufficientFragments);
+        assert_eq!(
+            fragments_needed_error(insufficient),
+            Error::InsufficientFragments
+        );
+
+        // `-EINVALIDPARAMS` (e.g., an invalid descriptor) and `-ENOMEM` are not hidden
+        let invalid_params = Error::InvalidParams.as_error_code();
+        assert_eq!(fragments_needed_error(invalid_params), Error::InvalidParams);
+        assert_eq!(fragments_needed_error(12), Error::Other(12));
+    }
+
+    #[test]
+    fn fragments_needed_fails() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        assert_eq!(coder.fragments_needed(&[6], &[]), Err(Error::InvalidParams));
+        assert_eq!(
+            coder.fragments_needed(&[0], &[6]),
+            Err(Error::InvalidParams)
+        );
+        assert_eq!(
+            coder.fragments_needed(&[0], &[1, 2]),
+            Err(Error::InsufficientFragments)
+        );
+    }
+
+    #[test]
+    fn malformed_fragments_are_rejected() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let data = vec![1; 1000];
+        let encoded = coder.encode(&data).unwrap();
+        let header_size = FragmentMetadata::HEADER_SIZE;
+
+        let mut malformeds = Vec::new();
+
+        // Fragments shorter than the header.
+        malformeds.push((
+            vec![Vec::new(); 4],
+            Error::InvalidFragment(0, FragmentError::InconsistentSize),
+        ));
+        malformeds.push((
+            encoded[..4]
+                .iter()
+                .map(|f| f[..header_size - 1].to_vec())
+                .collect(),
+            Error::InvalidFragment(0, FragmentError::InconsistentSize),
+        ));
+
+        // Fragments of different lengths.
+        let mut fragments = encoded[..4].to_vec();
+        fragments[1].pop();
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(1, FragmentError::InconsistentSize),
+        ));
+        let mut fragments = encoded[..4].to_vec();
+        fragments[3].extend_from_slice(&[0; 100]);
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(3, FragmentError::InconsistentSize),
+        ));
+        let mut fragments = encoded[..4].to_vec();
+        fragments[2].truncate(10);
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(2, FragmentError::InconsistentSize),
+        ));
+
+        // Bad magic number.
+        let mut fragments = encoded[..4].to_vec();
+        fragments[2][60] ^= 1;
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(2, FragmentError::BadMagic),
+        ));
+
+        // Payloads truncated to be shorter than the sizes recorded in the headers.
+        malformeds.push((
+            encoded[..4]
+                .iter()
+                .map(|f| f[..f.len() - 1].to_vec())
+                .collect(),
+            Error::InvalidFragment(0, FragmentError::InconsistentSize),
+        ));
+        malformeds.push((
+            encoded[..4]
+                .iter()
+                .map(|f| f[..header_size].to_vec())
+                .collect(),
+            Error::InvalidFragment(0, FragmentError::InconsistentSize),
+        ));
+
+        // Out of range index.
+        let mut fragments = encoded[..4].to_vec();
+        fragments[0][..4].copy_from_slice(&6u32.to_ne_bytes());
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(0, FragmentError::InvalidIndex),
+        ));
+        let mut fragments = encoded[..4].to_vec();
+        fragments[0][..4].copy_from_slice(&u32::MAX.to_ne_bytes());
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(0, FragmentError::InvalidIndex),
+        ));
+
+        // Oversized payload size.
+        let mut fragments = encoded[..4].to_vec();
+        fragments[1][4..8].copy_from_slice(&u32::MAX.to_ne_bytes());
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(1, FragmentError::InconsistentSize),
+        ));
+
+        // Oversized backend metadata size.
+        let mut fragments = encoded[..4].to_vec();
+        fragments[1][8..12].copy_from_slice(&u32::MAX.to_ne_bytes());
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(1, FragmentError::InconsistentSize),
+        ));
+
+        // Oversized original data size.
+        let mut fragments = encoded[..4].to_vec();
+        fragments[3][12..20].copy_from_slice(&u64::MAX.to_ne_bytes());
+        malformeds.push((
+            fragments,
+            Error::InvalidFragment(3, FragmentError::InvalidOriginalDataSize),
+        ));
+
+        for (fragments, error) in malformeds {
+            assert_eq!(coder.decode(&fragments), Err(error));
+            assert_eq!(coder.decode_borrowed(&fragments).err(), Some(error));
+            assert_eq!(coder.decode_into(&fragments, &mut Vec::new()), Err(error));
+            assert_eq!(coder.reconstruct(5, fragments.iter()), Err(error));
+            assert_eq!(
+                coder.reconstruct_many(&[4, 5], fragments.iter()),
+                Err(error)
+            );
+        }
+
+        // No fragments.
+        let fragments: Vec<Vec<u8>> = Vec::new();
+        assert_eq!(coder.decode(&fragments), Err(Error::InsufficientFragments));
+        assert_eq!(
+            coder.reconstruct(5, fragments.iter()),
+            Err(Error::InsufficientFragments)
+        );
+
+        // The original fragments are still decodable.
+        assert_eq!(coder.decode(&encoded[..4]), Ok(data));
+    }
+
     #[test]
     fn reconstruct_fails() {
-        let mut coder = ErasureCoder::new(non_zero(4), non_zero(4)).unwrap();
+        let coder = ErasureCoder::new(non_zero(4), non_zero(4)).unwrap();
         let data = vec![0, 1, 2, 3];
         let encoded = coder.encode(&data).unwrap();
 
@@ -423,38 +1681,1035 @@ mod tests {
 
     #[test]
     fn various_params() {
-        for backend in [Backend::JerasureRsCauchy, Backend::JerasureRsVand].iter() {
+        for backend in available_backends() {
             for checksum in [Checksum::None, Checksum::Crc32, Checksum::Md5].iter() {
-                for data_fragments in (3..6).map(non_zero) {
-                    for parity_fragments in (1..4).map(non_zero) {
-                        let mut coder = Builder::new(data_fragments, parity_fragments)
-                            .backend(*backend)
-                            .checksum(*checksum)
-                            .finish()
-                            .unwrap_or_else(|_| {
-                                panic!(
-                                    "Cannot make coder instance: k={}, m={}, b={:?}, \
-                                 c={:?}",
-                                    data_fragments, parity_fragments, backend, checksum
-                                )
-                            });
-
-                        let data = vec![0, 1, 2, 3];
-                        let encoded = coder.encode(&data).unwrap();
+                for (data_fragments, parity_fragments) in params(backend) {
+                    let coder = Builder::new(data_fragments, parity_fragments)
+                        .backend(backend)
+                        .checksum(*checksum)
+                        .finish()
+                        .unwrap_or_else(|_| {
+                            panic!(
+                                "Cannot make coder instance: k={}, m={}, b={:?}, c={:?}",
+                                data_fragments, parity_fragments, backend, checksum
+                            )
+                        });
+
+                    let data = vec![0, 1, 2, 3];
+                    let encoded = coder.encode(&data).unwrap();
 
-                        for i in 0..parity_fragments.get() {
-                            assert_eq!(Ok(&data), coder.decode(&encoded[i..]).as_ref());
-                        }
-                        assert_eq!(
-                            Err(Error::InsufficientFragments),
-                            coder.decode(&encoded[parity_fragments.get() + 1..])
-                        );
+                    for i in 0..=fault_tolerance(backend, parity_fragments.get()) {
+                        assert_eq!(Ok(&data), coder.decode(&encoded[i..]).as_ref());
                     }
+                    assert_eq!(
+                        Err(Error::InsufficientFragments),
+                        coder.decode(&encoded[parity_fragments.get() + 1..])
+                    );
                 }
             }
         }
     }
 
+    #[test]
+    fn reconstruct_works_for_various_backends() {
+        for backend in available_backends() {
+            if is_null(backend) {
+                // The null backend does not generate meaningful parity fragments.
+                continue;
+            }
+            for (data_fragments, parity_fragments) in params(backend) {
+                let coder = Builder::new(data_fragments, parity_fragments)
+                    .backend(backend)
+                    .finish()
+                    .unwrap();
+
+                let data = (0..0x1234).map(|i| i as u8).collect::<Vec<_>>();
+                let encoded = coder.encode(&data).unwrap();
+                for i in 0..coder.fragments().get() {
+                    let available = encoded
+                        .iter()
+                        .enumerate()
+                        .filter(|&(index, _)| index != i)
+                        .map(|(_, f)| f);
+                    assert_eq!(
+                        coder.reconstruct(i, available),
+                        Ok(encoded[i].clone()),
+                        "k={}, m={}, b={:?}, i={}",
+                        data_fragments,
+                        parity_fragments,
+                        backend,
+                        i
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn backend_availability_works() {
+        #[cfg(feature = "jerasure")]
+        {
+            assert!(Backend::JerasureRsVand.is_available());
+            assert!(Backend::JerasureRsCauchy.is_available());
+        }
+        #[cfg(feature = "flat-xor")]
+        assert!(Backend::FlatXorHd.is_available());
+        #[cfg(feature = "native")]
+        {
+            assert!(Backend::LiberasurecodeRsVand.is_available());
+            assert!(Backend::Null.is_available());
+        }
+        assert!(Backend::PureRustRs.is_available());
+
+        let mut backends = 1;
+        if cfg!(feature = "native") {
+            backends += 2;
+        }
+        if cfg!(feature = "jerasure") {
+            backends += 2;
+        }
+        if cfg!(feature = "flat-xor") {
+            backends += 1;
+        }
+        if cfg!(feature = "isa-l") {
+            backends += 2;
+        }
+        assert_eq!(Backend::all().count(), backends);
+
+        for backend in Backend::all() {
+            let result = Builder::new(non_zero(3), non_zero(3))
+                .backend(backend)
+                .finish();
+            if backend.is_available() {
+                assert!(result.is_ok(), "b={:?}", backend);
+            } else {
+                assert_eq!(result.err(), Some(Error::BackendNotAvailable));
+            }
+        }
+    }
+
+    #[test]
+    fn fragment_metadata_works() {
+        let coder = Builder::new(non_zero(4), non_zero(2))
+            .checksum(Checksum::Crc32)
+            .finish()
+            .unwrap();
+        let data = vec![0; 1000];
+        let encoded = coder.encode(&data).unwrap();
+
+        for (i, fragment) in encoded.iter().enumerate() {
+            let metadata = coder.fragment_metadata(fragment).unwrap();
+            assert_eq!(metadata.index(), i);
+            assert_eq!(metadata.original_data_size(), data.len() as u64);
+            assert_eq!(
+                FragmentMetadata::HEADER_SIZE + metadata.size() + metadata.backend_metadata_size(),
+                fragment.len()
+            );
+            assert_eq!(metadata.checksum_type(), Checksum::Crc32);
+            assert_eq!(metadata.checksum().len(), 1);
+            assert!(!metadata.checksum_mismatch());
+            assert_eq!(metadata.backend(), Some(Builder::DEFAULT_BACKEND));
+            assert_eq!(metadata.libec_version() >> 16, 1);
+        }
+
+        // Corrupted payload
+        let mut fragment = encoded[0].clone();
+        fragment[FragmentMetadata::HEADER_SIZE] ^= 1;
+        assert!(coder
+            .fragment_metadata(&fragment)
+            .unwrap()
+            .checksum_mismatch());
+
+        // Broken magic
+        let mut fragment = encoded[0].clone();
+        fragment[60] ^= 1;
+        assert_eq!(coder.fragment_metadata(&fragment), Err(Error::BadHeader));
+
+        // Truncated fragments
+        let fragment = &encoded[0];
+        assert_eq!(
+            coder.fragment_metadata(&fragment[..FragmentMetadata::HEADER_SIZE - 1]),
+            Err(Error::BadHeader)
+        );
+        assert_eq!(
+            coder.fragment_metadata(&fragment[..FragmentMetadata::HEADER_SIZE + 1]),
+            Err(Error::BadHeader)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn reference_vectors_are_reproduced() {
+        for vector in reference_vectors() {
+            let backend = match vector.backend {
+                Some(backend) if backend.is_available() => backend,
+                _ => continue,
+            };
+            let coder = Builder::new(vector.data_fragments, vector.parity_fragments)
+                .backend(backend)
+                .checksum(vector.checksum)
+                .finish()
+                .unwrap();
+
+            let encoded = coder.encode(&vector.data).unwrap();
+            assert_eq!(encoded.len(), vector.fragments.len(), "{}", vector.name);
+            for (i, (actual, expected)) in encoded.iter().zip(vector.fragments.iter()).enumerate() {
+                assert!(
+                    actual == expected,
+                    "{}: fragment {} differs",
+                    vector.name,
+                    i
+                );
+            }
+
+            let tolerance = fault_tolerance(backend, vector.parity_fragments.get());
+            assert_eq!(
+                coder.decode(&vector.fragments).as_ref(),
+                Ok(&vector.data),
+                "{}",
+                vector.name
+            );
+            assert_eq!(
+                coder.decode(&vector.fragments[tolerance..]).as_ref(),
+                Ok(&vector.data),
+                "{}",
+                vector.name
+            );
+        }
+    }
+
+    #[test]
+    fn verify_stripe_works() {
+        let coder = Builder::new(non_zero(4), non_zero(4))
+            .checksum(Checksum::Crc32)
+            .finish()
+            .unwrap();
+        let encoded = coder.encode(&vec![0; 1000]).unwrap();
+        assert!(encoded.iter().all(|f| coder.is_valid_fragment(f)));
+        assert!(coder.verify_stripe(&encoded).unwrap().is_valid());
+
+        let mut fragments = encoded.clone();
+        fragments[0][60] ^= 1; // magic
+        fragments[1][0] ^= 1; // index (covered by the header checksum)
+        fragments[2][FragmentMetadata::HEADER_SIZE] ^= 1; // payload
+        fragments[3].push(0); // length
+        let other = coder.encode(&vec![0; 2000]).unwrap();
+        fragments[4] = other[4].clone(); // another stripe
+        fragments[5][metadata::LIBEC_VERSION_OFFSET..][..4].copy_from_slice(&[0; 4]); // version
+        assert!(!coder.is_valid_fragment(&fragments[0]));
+        assert!(!coder.is_valid_fragment(&fragments[1]));
+        assert!(!coder.is_valid_fragment(&fragments[2]));
+        assert!(!coder.is_valid_fragment(&fragments[3]));
+        assert!(coder.is_valid_fragment(&fragments[4]));
+        assert!(!coder.is_valid_fragment(&fragments[5]));
+        assert!(!coder.is_valid_fragment(&[]));
+
+        let report = coder.verify_stripe(&fragments).unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.invalid_fragments(),
+            &[
+                (0, FragmentError::BadMagic),
+                (1, FragmentError::BadHeaderChecksum),
+                (2, FragmentError::BadChecksum),
+                (3, FragmentError::InconsistentSize),
+                (4, FragmentError::InconsistentSize),
+                (5, FragmentError::IncompatibleVersion),
+            ]
+        );
+
+        #[cfg(feature = "native")]
+        {
+            let other_coder = Builder::new(non_zero(4), non_zero(4))
+                .backend(Backend::Null)
+                .finish()
+                .unwrap();
+            let other = other_coder.encode(&vec![0; 1000]).unwrap();
+            assert_eq!(
+                coder
+                    .verify_stripe(&other[..1])
+                    .unwrap()
+                    .invalid_fragments(),
+                &[(0, FragmentError::BackendMismatch)]
+            );
+        }
+        assert_eq!(
+            coder.verify_stripe(&Vec::<Vec<u8>>::new()),
+            Err(Error::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn decode_with_checksum_verification_works() {
+        let coder = Builder::new(non_zero(4), non_zero(2))
+            .checksum(Checksum::Crc32)
+            .finish()
+            .unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut encoded = coder.encode(&data).unwrap();
+
+        let mut options = DecodeOptions::new();
+        options.verify_checksum(true);
+        assert_eq!(coder.decode_with(&encoded, &options), Ok(data.clone()));
+
+        encoded[0][FragmentMetadata::HEADER_SIZE] ^= 1;
+        assert_ne!(coder.decode(&encoded[..4]), Ok(data.clone()));
+        assert_eq!(
+            coder.decode_with(&encoded, &options),
+            Err(Error::BadChecksum)
+        );
+
+        // Fragments without checksums are rejected
+        let other_coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let other = other_coder.encode(&data).unwrap();
+        assert_eq!(coder.decode_with(&other, &options), Err(Error::BadChecksum));
+    }
+
+    #[test]
+    fn decode_with_md5_checksum_verification_fails() {
+        let coder = Builder::new(non_zero(4), non_zero(2))
+            .checksum(Checksum::Md5)
+            .finish()
+            .unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut encoded = coder.encode(&data).unwrap();
+        encoded[0][FragmentMetadata::HEADER_SIZE] ^= 1;
+
+        // The corrupted payload must not be decoded as if it had been verified
+        let mut options = DecodeOptions::new();
+        options.verify_checksum(true);
+        assert_eq!(
+            coder.decode_with(&encoded, &options),
+            Err(Error::EcMethodNotImplemented)
+        );
+        assert_ne!(coder.decode(&encoded[..4]), Ok(data));
+    }
+
+    #[test]
+    fn decode_with_metadata_checks_works() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut encoded = coder.encode(&data).unwrap();
+
+        let mut options = DecodeOptions::new();
+        options.force_metadata_checks(true);
+        assert_eq!(coder.decode_with(&encoded[..4], &options), Ok(data.clone()));
+
+        // Breaks the header checksum of a fragment
+        encoded[0][0] ^= 1;
+        assert_eq!(
+            coder.decode_with(&encoded[..4], &options),
+            Err(Error::InsufficientFragments)
+        );
+    }
+
+    #[test]
+    fn decode_excluding_corrupted_works() {
+        let coder = Builder::new(non_zero(4), non_zero(2))
+            .checksum(Checksum::Crc32)
+            .finish()
+            .unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut encoded = coder.encode(&data).unwrap();
+
+        let recovered = coder.decode_excluding_corrupted(&encoded).unwrap();
+        assert_eq!(recovered.data(), &data[..]);
+        assert!(recovered.corrupted_fragments().is_empty());
+
+        encoded[1][FragmentMetadata::HEADER_SIZE + 10] ^= 1; // payload
+        encoded[3][0] ^= 1; // header
+        let recovered = coder.decode_excluding_corrupted(&encoded).unwrap();
+        assert_eq!(
+            recovered.corrupted_fragments(),
+            &[CorruptedFragment::Index(1), CorruptedFragment::Position(3)]
+        );
+        assert_eq!(recovered.into_data(), data);
+
+        // The indices in the headers are reported for a shuffled subset
+        let subset = [
+            &encoded[5],
+            &encoded[1],
+            &encoded[0],
+            &encoded[2],
+            &encoded[4],
+        ];
+        let recovered = coder.decode_excluding_corrupted(&subset).unwrap();
+        assert_eq!(
+            recovered.corrupted_fragments(),
+            &[CorruptedFragment::Index(1)]
+        );
+        assert_eq!(recovered.into_data(), data);
+
+        encoded[4][FragmentMetadata::HEADER_SIZE] ^= 1;
+        assert_eq!(
+            coder.decode_excluding_corrupted(&encoded),
+            Err(Error::InsufficientFragments)
+        );
+
+        // Fragments of other stripes have valid headers, but disagree with the majority
+        let mut encoded = coder.encode(&data).unwrap();
+        encoded[0] = coder.encode(&data[..999]).unwrap().swap_remove(0); // orig_data_size
+        encoded[2] = coder.encode(&vec![0; 2000]).unwrap().swap_remove(2); // length
+        assert_eq!(encoded[0].len(), encoded[1].len());
+        let recovered = coder.decode_excluding_corrupted(&encoded).unwrap();
+        assert_eq!(
+            recovered.corrupted_fragments(),
+            &[CorruptedFragment::Index(0), CorruptedFragment::Index(2)]
+        );
+        assert_eq!(recovered.into_data(), data);
+
+        // MD5 digests cannot be verified
+        let coder = Builder::new(non_zero(4), non_zero(2))
+            .checksum(Checksum::Md5)
+            .finish()
+            .unwrap();
+        let encoded = coder.encode(&data).unwrap();
+        assert_eq!(
+            coder.decode_excluding_corrupted(&encoded),
+            Err(Error::EcMethodNotImplemented)
+        );
+    }
+
+    #[test]
+    fn size_calculation_works() {
+        for backend in available_backends() {
+            for (data_fragments, parity_fragments) in params(backend) {
+                let coder = Builder::new(data_fragments, parity_fragments)
+                    .backend(backend)
+                    .finish()
+                    .unwrap();
+                let minimum_encode_size = coder.minimum_encode_size().unwrap();
+                assert!(minimum_encode_size > 0);
+                assert_eq!(coder.aligned_data_size(1), Ok(minimum_encode_size));
+
+                for &len in [1, 4, 1000, 0xc0de].iter() {
+                    let aligned_data_size = coder.aligned_data_size(len).unwrap();
+                    assert!(aligned_data_size >= len);
+                    assert_eq!(aligned_data_size % data_fragments.get(), 0);
+
+                    let encoded = coder.encode(&vec![0; len]).unwrap();
+                    let fragment_size = coder.fragment_size(len).unwrap();
+                    assert!(encoded.iter().all(|f| f.len() == fragment_size));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn encode_into_works() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let encoded = coder.encode(&data).unwrap();
+
+        let fragment_size = coder.fragment_size(data.len()).unwrap();
+        let mut buf = vec![0xff; (fragment_size + 1) * 6];
+        let mut fragments = buf.chunks_mut(fragment_size + 1).collect::<Vec<_>>();
+        assert_eq!(coder.encode_into(&data, &mut fragments), Ok(fragment_size));
+        for (fragment, expected) in fragments.iter().zip(encoded.iter()) {
+            assert_eq!(&fragment[..fragment_size], &expected[..]);
+            assert_eq!(fragment[fragment_size], 0xff);
+        }
+
+        // Too few buffers
+        assert_eq!(
+            coder.encode_into(&data, &mut fragments[..5]),
+            Err(Error::InvalidParams)
+        );
+
+        // Too short buffers
+        let mut buf = vec![0; (fragment_size - 1) * 6];
+        let mut fragments = buf.chunks_mut(fragment_size - 1).collect::<Vec<_>>();
+        assert_eq!(
+            coder.encode_into(&data, &mut fragments),
+            Err(Error::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn pure_rust_encode_into_does_not_allocate_fragments() {
+        let data = (0..0x10000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+        for (data_fragments, parity_fragments) in params(Backend::PureRustRs) {
+            let coder = Builder::new(data_fragments, parity_fragments)
+                .backend(Backend::PureRustRs)
+                .checksum(Checksum::Crc32)
+                .finish()
+                .unwrap();
+            let expected = coder.encode(&data).unwrap();
+            let fragment_size = coder.fragment_size(data.len()).unwrap();
+
+            // The buffers are filled with garbage which must be overwritten (except the last byte)
+            let mut buf = vec![0xa5; (fragment_size + 1) * coder.fragments().get()];
+            let mut fragments = buf.chunks_mut(fragment_size + 1).collect::<Vec<_>>();
+            let before = allocated_bytes();
+            let result = coder.encode_into(&data, &mut fragments);
+            let allocated = allocated_bytes() - before;
+            assert_eq!(result, Ok(fragment_size));
+
+            // Only the lists of the slices may be allocated (not the fragments themselves)
+            assert!(
+                allocated < fragment_size - FragmentMetadata::HEADER_SIZE,
+                "k={}, m={}, allocated={}",
+                data_fragments,
+                parity_fragments,
+                allocated
+            );
+            for (fragment, expected) in fragments.iter().zip(expected.iter()) {
+                assert_eq!(&fragment[..fragment_size], &expected[..]);
+                assert_eq!(fragment[fragment_size], 0xa5);
+            }
+        }
+    }
+
+    #[test]
+    fn encode_borrowed_works() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let expected = coder.encode(&data).unwrap();
+
+        let encoded = coder.encode_borrowed(&data).unwrap();
+        assert_eq!(encoded.len(), 6);
+        assert_eq!(encoded.fragment_len(), expected[0].len());
+        assert!(encoded.iter().eq(expected.iter().map(|f| &f[..])));
+        assert_eq!(&encoded[5], &expected[5][..]);
+        assert_eq!(encoded.get(6), None);
+
+        drop(encoded);
+        assert_eq!(coder.decode(&expected[2..]), Ok(data));
+    }
+
+    #[test]
+    fn decode_borrowed_works() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let encoded = coder.encode(&data).unwrap();
+
+        let decoded = coder.decode_borrowed(&encoded[2..]).unwrap();
+        assert_eq!(&decoded[..], &data[..]);
+        drop(decoded);
+
+        assert_eq!(
+            coder.decode_borrowed(&encoded[3..]).err(),
+            Some(Error::InsufficientFragments)
+        );
+    }
+
+    #[test]
+    fn decode_into_works() {
+        let coder = ErasureCoder::new(non_zero(4), non_zero(2)).unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let encoded = coder.encode(&data).unwrap();
+
+        let mut buf = vec![0xff; 10];
+        coder.decode_into(&encoded[1..], &mut buf).unwrap();
+        assert_eq!(buf, data);
+
+        let capacity = buf.capacity();
+        let encoded = coder.encode(&data[..100]).unwrap();
+        coder.decode_into(&encoded, &mut buf).unwrap();
+        assert_eq!(buf, &data[..100]);
+        assert_eq!(buf.capacity(), capacity);
+    }
+
+    #[test]
+    fn coder_is_send_and_sync() {
+        fn assert_send_and_sync<T: Send + Sync>() {}
+        assert_send_and_sync::<ErasureCoder>();
+        assert_send_and_sync::<EncodedFragments>();
+        assert_send_and_sync::<DecodedData>();
+    }
+
+    #[test]
+    fn coder_can_be_shared_by_threads() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let coder = Arc::new(ErasureCoder::new(non_zero(4), non_zero(2)).unwrap());
+        let handles = (0..8)
+            .map(|i| {
+                let coder = Arc::clone(&coder);
+                thread::spawn(move || {
+                    for j in 0..100 {
+                        let data = vec![i as u8; 1000 + j];
+                        let encoded = coder.encode(&data).unwrap();
+                        assert_eq!(coder.decode(&encoded[2..]), Ok(data));
+                        let reconstructed = coder.reconstruct(0, encoded[1..5].iter());
+                        assert_eq!(reconstructed, Ok(encoded[0].clone()));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(not(liberasurecode_system))]
+    fn coders_can_be_created_concurrently() {
+        use std::sync::{Arc, Barrier};
+        use std::thread;
+
+        let backends = available_backends()
+            .flat_map(|backend| params(backend).into_iter().map(move |p| (backend, p)))
+            .collect::<Vec<_>>();
+        let threads = 16;
+        let barrier = Arc::new(Barrier::new(threads));
+        let handles = (0..threads)
+            .map(|i| {
+                let backends = backends.clone();
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    for j in 0..32 {
+                        let (backend, (k, m)) = backends[(i * 32 + j) % backends.len()];
+                        let coder = Builder::new(k, m).backend(backend).finish().unwrap();
+                        let data = vec![j as u8; 1000 + i];
+                        let encoded = coder.encode(&data).unwrap();
+                        let lost = fault_tolerance(backend, m.get());
+                        assert_eq!(coder.decode(&encoded[lost..]), Ok(data));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn coder_cache_works() {
+        let cache = CoderCache::new();
+        assert!(cache.is_empty());
+
+        let mut builder = Builder::new(non_zero(4), non_zero(2));
+        let coder0 = cache.get(&builder).unwrap();
+        let coder1 = cache.get(&builder).unwrap();
+        assert!(Arc::ptr_eq(&coder0, &coder1));
+        assert_eq!(cache.len(), 1);
+
+        builder.checksum(Checksum::Crc32);
+        let coder2 = cache.get(&builder).unwrap();
+        assert!(!Arc::ptr_eq(&coder0, &coder2));
+        assert_eq!(coder2.checksum, Checksum::Crc32);
+        assert_eq!(cache.len(), 2);
+
+        let data = vec![1; 1000];
+        let encoded = coder0.encode(&data).unwrap();
+        assert_eq!(coder1.decode(&encoded[2..]), Ok(data));
+
+        std::mem::drop(coder0);
+        assert_eq!(cache.len(), 2);
+        std::mem::drop(coder1);
+        assert_eq!(cache.len(), 1);
+        std::mem::drop(coder2);
+        assert!(cache.is_empty());
+
+        let invalid = Builder::new(non_zero(1), non_zero(1));
+        assert_eq!(cache.get(&invalid).err(), Some(Error::InvalidParams));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn shared_coder_works() {
+        use std::thread;
+
+        let builder = Builder::new(non_zero(5), non_zero(3));
+        let coder = ErasureCoder::shared(&builder).unwrap();
+        let handles = (0..8)
+            .map(|_| {
+                let builder = builder.clone();
+                thread::spawn(move || ErasureCoder::shared(&builder).unwrap())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert!(Arc::ptr_eq(&coder, &handle.join().unwrap()));
+        }
+    }
+
+    #[test]
+    fn invalid_params() {
+        let invalids = [
+            (Builder::DEFAULT_BACKEND, 1, 1),
+            #[cfg(feature = "native")]
+            (Backend::LiberasurecodeRsVand, 65500, 100),
+            #[cfg(feature = "flat-xor")]
+            (Backend::FlatXorHd, 4, 2),
+            #[cfg(feature = "flat-xor")]
+            (Backend::FlatXorHd, 4, 3),
+            #[cfg(feature = "flat-xor")]
+            (Backend::FlatXorHd, 4, 5),
+            #[cfg(feature = "flat-xor")]
+            (Backend::FlatXorHd, 21, 6),
+            #[cfg(feature = "isa-l")]
+            (Backend::IsaLRsVand, 10, 5),
+            #[cfg(feature = "isa-l")]
+            (Backend::IsaLRsCauchy, 250, 7),
+        ];
+        for &(backend, k, m) in invalids.iter() {
+            assert_eq!(
+                Builder::new(non_zero(k), non_zero(m))
+                    .backend(backend)
+                    .finish()
+                    .err(),
+                Some(Error::InvalidParams),
+                "k={}, m={}, b={:?}",
+                k,
+                m,
+                backend
+            );
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn roundtrip_works(
+            backend in any::<usize>(),
+            data_fragments in any::<usize>(),
+            parity_fragments in any::<usize>(),
+            checksum in prop_oneof![
+                Just(Checksum::None),
+                Just(Checksum::Crc32),
+                Just(Checksum::Md5)
+            ],
+            data_len in prop_oneof![
+                Just(0usize),
+                1..64usize,
+                64..0x10000usize,
+                0x400000..0x400040usize
+            ],
+            seed in any::<u64>()
+        ) {
+            let backends = available_backends().collect::<Vec<_>>();
+            let backend = backends[backend % backends.len()];
+            let (k, m) = random_params(backend, data_fragments, parity_fragments);
+            check_roundtrip(backend, k, m, checksum, data_len, seed);
+        }
+    }
+
+    /// Checks that the data encoded by the given coder is recovered from surviving fragments.
+    ///
+    /// The data and the erasure pattern are derived from `seed`.
+    fn check_roundtrip(
+        backend: Backend,
+        data_fragments: usize,
+        parity_fragments: usize,
+        checksum: Checksum,
+        data_len: usize,
+        seed: u64,
+    ) {
+        let coder = match Builder::new(non_zero(data_fragments), non_zero(parity_fragments))
+            .backend(backend)
+            .checksum(checksum)
+            .finish()
+        {
+            Ok(coder) => coder,
+            Err(e) => {
+                assert_eq!(e, Error::InvalidParams);
+                return;
+            }
+        };
+        let mut rng = XorShift(seed | 1);
+        let data = (0..data_len)
+            .map(|_| rng.next_u64() as u8)
+            .collect::<Vec<_>>();
+        let encoded = coder.encode(&data).unwrap();
+        let n = coder.fragments().get();
+        assert_eq!(encoded.len(), n);
+
+        // Shuffles the fragments and keeps a random number of them.
+        let mut order = (0..n).collect::<Vec<_>>();
+        for i in (1..n).rev() {
+            order.swap(i, rng.next_u64() as usize % (i + 1));
+        }
+        let survived = rng.next_u64() as usize % (n + 1);
+        let (survivors, losts) = order.split_at(survived);
+        let fragments = survivors.iter().map(|&i| &encoded[i]).collect::<Vec<_>>();
+        let recoverable = losts.len() <= fault_tolerance(backend, parity_fragments);
+        let context = format!(
+            "k={}, m={}, b={:?}, c={:?}, len={}, survivors={:?}",
+            data_fragments, parity_fragments, backend, checksum, data_len, survivors
+        );
+
+        let decoded = coder.decode(&fragments);
+        if survivors.len() < data_fragments {
+            assert_eq!(decoded, Err(Error::InsufficientFragments), "{}", context);
+        } else if recoverable {
+            assert_eq!(decoded.as_ref(), Ok(&data), "{}", context);
+        } else if !is_null(backend) {
+            // Some combinations are not decodable by non-MDS backends, but decoding must
+            // never result in wrong data.
+            if let Ok(decoded) = decoded {
+                assert_eq!(decoded, data, "{}", context);
+            }
+        }
+
+        if is_null(backend) {
+            // The null backend does not generate meaningful parity fragments.
+            return;
+        }
+        for &i in losts {
+            let reconstructed = coder.reconstruct(i, fragments.iter());
+            if survivors.len() < data_fragments {
+                assert_eq!(
+                    reconstructed,
+                    Err(Error::InsufficientFragments),
+                    "{}",
+                    context
+                );
+            } else if recoverable {
+                assert_eq!(
+                    reconstructed.as_ref(),
+                    Ok(&encoded[i]),
+                    "{}, i={}",
+                    context,
+                    i
+                );
+            } else if let Ok(reconstructed) = reconstructed {
+                assert_eq!(reconstructed, encoded[i], "{}, i={}", context, i);
+            }
+        }
+        if recoverable && survivors.len() >= data_fragments {
+            let expected = losts
+                .iter()
+                .map(|&i| (i, encoded[i].clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                coder.reconstruct_many(losts, fragments.iter()),
+                Ok(expected),
+                "{}",
+                context
+            );
+        }
+    }
+
+    /// Maps the given random numbers to `(data_fragments, parity_fragments)` for the backend.
+    fn random_params(backend: Backend, k: usize, m: usize) -> (usize, usize) {
+        match backend {
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd => match m % 3 {
+                0 => (3, 3),
+                1 => (5 + k % 6, 5),
+                _ => (6 + k % 15, 6),
+            },
+            #[cfg(feature = "isa-l")]
+            Backend::IsaLRsVand => (1 + k % 16, 1 + m % 4),
+            _ => (1 + k % 16, 1 + m % 6),
+        }
+    }
+
+    /// Decodes from every combination of `data_fragments` fragments, and reconstructs the rest.
+    fn check_all_combinations(
+        backend: Backend,
+        data_fragments: usize,
+        parity_fragments: usize,
+        checksum: Checksum,
+    ) -> Result<()> {
+        let (k, m) = (data_fragments, parity_fragments);
+        let coder = Builder::new(non_zero(k), non_zero(m))
+            .backend(backend)
+            .checksum(checksum)
+            .finish()?;
+        let mut rng = XorShift(0x1234_5678 + k as u64);
+        let data = (0..0x1000 + k)
+            .map(|_| rng.next_u64() as u8)
+            .collect::<Vec<_>>();
+        let encoded = coder.encode(&data)?;
+        assert_eq!(encoded.len(), k + m);
+        for fragment in &encoded {
+            let metadata = coder.fragment_metadata(fragment)?;
+            assert_eq!(metadata.backend(), Some(backend));
+            assert_eq!(metadata.checksum_type(), checksum);
+            assert!(!metadata.checksum_mismatch());
+        }
+
+        for alive in 0usize..1 << (k + m) {
+            if alive.count_ones() as usize != k {
+                continue;
+            }
+            let fragments = (0..k + m)
+                .filter(|i| alive & 1 << i != 0)
+                .map(|i| &encoded[i])
+                .collect::<Vec<_>>();
+            let context = format!("b={:?}, k={}, m={}, alive={:b}", backend, k, m, alive);
+            assert_eq!(coder.decode(&fragments)?, data, "{}", context);
+            for i in (0..k + m).filter(|i| alive & 1 << i == 0) {
+                let reconstructed = coder.reconstruct(i, fragments.iter().cloned())?;
+                assert_eq!(reconstructed, encoded[i], "i={}, {}", i, context);
+            }
+        }
+        Ok(())
+    }
+
+    /// A tiny xorshift PRNG used for generating test data reproducibly.
+    struct XorShift(u64);
+    impl XorShift {
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    /// Returns `(data_fragments, parity_fragments)` pairs supported by the given backend.
+    fn params(backend: Backend) -> Vec<(NonZeroUsize, NonZeroUsize)> {
+        let params = match backend {
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd => vec![(3, 3), (5, 5), (10, 5), (6, 6), (12, 6)],
+            _ => (3..6)
+                .flat_map(|k| (1..4).map(move |m| (k, m)))
+                .collect::<Vec<_>>(),
+        };
+        params
+            .into_iter()
+            .map(|(k, m)| (non_zero(k), non_zero(m)))
+            .collect()
+    }
+
+    /// A fragment set generated by the reference liberasurecode (see `testdata/generate_vectors.py`).
+    #[cfg(feature = "native")]
+    struct ReferenceVector {
+        name: String,
+
+        /// `None` if the backend is disabled by the cargo features.
+        backend: Option<Backend>,
+        checksum: Checksum,
+        data_fragments: NonZeroUsize,
+        parity_fragments: NonZeroUsize,
+        data: Vec<u8>,
+        fragments: Vec<Vec<u8>>,
+    }
+
+    /// Loads the fragment sets in `testdata/vectors`.
+    ///
+    /// Panics if there are no sets (i.e., the vectors have not been generated).
+    #[cfg(feature = "native")]
+    fn reference_vectors() -> Vec<ReferenceVector> {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/vectors");
+        let entries = std::fs::read_dir(&dir).unwrap_or_else(|e| {
+            panic!(
+                "Cannot read the reference vectors in {:?} (see `testdata/generate_vectors.py`): {}",
+                dir, e
+            )
+        });
+        let mut set_dirs = entries
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+        assert!(!set_dirs.is_empty(), "No reference vectors in {:?}", dir);
+        set_dirs.sort();
+
+        set_dirs
+            .into_iter()
+            .map(|set_dir| {
+                let name = set_dir.file_name().unwrap().to_string_lossy().into_owned();
+                let params = std::fs::read_to_string(set_dir.join("params")).unwrap();
+                let param = |key: &str| {
+                    params
+                        .lines()
+                        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                        .unwrap_or_else(|| panic!("{}: `{}` is not found", name, key))
+                        .to_owned()
+                };
+                let backend = Backend::all().find(|backend| {
+                    format!("{:?}", backend) == backend_type_name(&param("backend"))
+                });
+                let checksum = match param("checksum").as_str() {
+                    "none" => Checksum::None,
+                    "crc32" => Checksum::Crc32,
+                    "md5" => Checksum::Md5,
+                    checksum => panic!("{}: unknown checksum `{}`", name, checksum),
+                };
+                let data_fragments = non_zero(param("data_fragments").parse().unwrap());
+                let parity_fragments = non_zero(param("parity_fragments").parse().unwrap());
+                let data = std::fs::read(set_dir.join("data.bin")).unwrap();
+                let fragments = (0..data_fragments.get() + parity_fragments.get())
+                    .map(|i| std::fs::read(set_dir.join(format!("fragment-{}.bin", i))).unwrap())
+                    .collect();
+                ReferenceVector {
+                    name,
+                    backend,
+                    checksum,
+                    data_fragments,
+                    parity_fragments,
+                    data,
+                    fragments,
+                }
+            })
+            .collect()
+    }
+
+    /// Converts a backend name of liberasurecode (e.g., `jerasure_rs_vand`)
+    /// into the name of the corresponding `Backend` variant (e.g., `JerasureRsVand`).
+    #[cfg(feature = "native")]
+    fn backend_type_name(name: &str) -> String {
+        name.split('_')
+            .map(|word| match word {
+                "isa" => "IsaL".to_owned(),
+                "l" => String::new(),
+                _ => word[..1].to_uppercase() + &word[1..],
+            })
+            .collect()
+    }
+
+    /// Returns the number of lost fragments the backend can always recover from.
+    fn fault_tolerance(backend: Backend, parity_fragments: usize) -> usize {
+        match backend {
+            #[cfg(feature = "native")]
+            Backend::Null => 0,
+            #[cfg(feature = "flat-xor")]
+            Backend::FlatXorHd => backend.hamming_distance(parity_fragments) - 1,
+            _ => parity_fragments,
+        }
+    }
+
+    #[cfg(feature = "native")]
+    fn is_null(backend: Backend) -> bool {
+        backend == Backend::Null
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn is_null(_backend: Backend) -> bool {
+        false
+    }
+
+    /// Counts the bytes allocated by each thread (to check that no fragments are allocated).
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATED_BYTES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATED_BYTES.try_with(|n| n.set(n.get() + layout.size()));
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(
+            &self,
+            ptr: *mut u8,
+            layout: std::alloc::Layout,
+            new_size: usize,
+        ) -> *mut u8 {
+            let _ = ALLOCATED_BYTES.try_with(|n| n.set(n.get() + new_size));
+            std::alloc::System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocated_bytes() -> usize {
+        ALLOCATED_BYTES.with(|n| n.get())
+    }
+
     fn non_zero(n: usize) -> NonZeroUsize {
         NonZeroUsize::new(n).expect("Must be a non zero number")
     }
@@ -0,0 +1,279 @@
+use crate::crc32::crc32;
+use crate::{Backend, Checksum, Error, FragmentError, Result};
+
+/// The size of `fragment_header_t` of [openstack/liberasurecode].
+///
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+pub(crate) const FRAGMENT_HEADER_SIZE: usize = 80;
+
+/// The size of `fragment_metadata_t` located at the beginning of `fragment_header_t`.
+pub(crate) const FRAGMENT_METADATA_SIZE: usize = 59;
+
+/// The maximum number of the 32-bit words of a checksum in `fragment_metadata_t`.
+pub(crate) const MAX_CHECKSUM_LEN: usize = 8;
+
+/// The offsets of the fields in `fragment_header_t`.
+pub(crate) const INDEX_OFFSET: usize = 0;
+pub(crate) const SIZE_OFFSET: usize = 4;
+pub(crate) const BACKEND_METADATA_SIZE_OFFSET: usize = 8;
+pub(crate) const ORIG_DATA_SIZE_OFFSET: usize = 12;
+pub(crate) const CHECKSUM_TYPE_OFFSET: usize = 20;
+pub(crate) const CHECKSUM_OFFSET: usize = 21;
+pub(crate) const CHECKSUM_MISMATCH_OFFSET: usize = 53;
+pub(crate) const BACKEND_ID_OFFSET: usize = 54;
+pub(crate) const BACKEND_VERSION_OFFSET: usize = 55;
+pub(crate) const MAGIC_OFFSET: usize = 59;
+pub(crate) const LIBEC_VERSION_OFFSET: usize = 63;
+pub(crate) const METADATA_CHECKSUM_OFFSET: usize = 67;
+
+/// The value of the `magic` field in `fragment_header_t`.
+pub(crate) const FRAGMENT_HEADER_MAGIC: u32 = 0x0b0c_5ecc;
+
+/// The first version of liberasurecode which stores `metadata_chksum` in fragment headers.
+pub(crate) const METADATA_CHECKSUM_MIN_LIBEC_VERSION: u32 = 0x01_02_00;
+
+/// The version of liberasurecode whose fragment format this crate follows.
+pub(crate) const LIBERASURECODE_VERSION: u32 = 0x01_05_00;
+
+/// The values of `ec_checksum_type_t`.
+pub(crate) const CHECKSUM_TYPE_NONE: u8 = 1;
+pub(crate) const CHECKSUM_TYPE_CRC32: u8 = 2;
+pub(crate) const CHECKSUM_TYPE_MD5: u8 = 3;
+
+/// Metadata stored in the header of a fragment.
+///
+/// Every fragment generated by [`ErasureCoder::encode`] starts with a fixed size header
+/// (`fragment_header_t` in [openstack/liberasurecode]) that holds this metadata.
+///
+/// [`ErasureCoder::encode`]: ./struct.ErasureCoder.html#method.encode
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FragmentMetadata {
+    index: usize,
+    size: usize,
+    backend_metadata_size: usize,
+    original_data_size: u64,
+    checksum_type: Checksum,
+    checksum: [u32; MAX_CHECKSUM_LEN],
+    checksum_mismatch: bool,
+    backend: Option<Backend>,
+    backend_version: u32,
+    libec_version: u32,
+}
+impl FragmentMetadata {
+    /// The size of the header located at the beginning of each fragment.
+    pub const HEADER_SIZE: usize = FRAGMENT_HEADER_SIZE;
+
+    /// Parses the header of the given fragment.
+    ///
+    /// This follows `liberasurecode_get_fragment_metadata` of [openstack/liberasurecode]
+    /// (e.g., the CRC32 checksum of the payload is verified), but does not call it
+    /// so that this also works without the native libraries.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub(crate) fn parse(fragment: &[u8]) -> Result<Self> {
+        check_header(fragment).map_err(|_| Error::BadHeader)?;
+        if !is_valid_header_checksum(fragment) {
+            return Err(Error::BadHeader);
+        }
+
+        let checksum_type = match fragment[CHECKSUM_TYPE_OFFSET] {
+            0 | CHECKSUM_TYPE_NONE => Checksum::None,
+            CHECKSUM_TYPE_CRC32 => Checksum::Crc32,
+            CHECKSUM_TYPE_MD5 => Checksum::Md5,
+            _ => return Err(Error::BadHeader),
+        };
+        let mut checksum = [0; MAX_CHECKSUM_LEN];
+        for (i, word) in checksum.iter_mut().enumerate() {
+            *word = read_u32(fragment, CHECKSUM_OFFSET + i * 4);
+        }
+        let size = read_u32(fragment, SIZE_OFFSET) as usize;
+        let mut checksum_mismatch = fragment[CHECKSUM_MISMATCH_OFFSET] != 0;
+        if checksum_type == Checksum::Crc32 {
+            let payload = &fragment[FRAGMENT_HEADER_SIZE..][..size];
+            checksum_mismatch |= crc32(payload) != checksum[0];
+        }
+        Ok(FragmentMetadata {
+            index: read_u32(fragment, INDEX_OFFSET) as usize,
+            size,
+            backend_metadata_size: read_u32(fragment, BACKEND_METADATA_SIZE_OFFSET) as usize,
+            original_data_size: read_u64(fragment, ORIG_DATA_SIZE_OFFSET),
+            checksum_type,
+            checksum,
+            checksum_mismatch,
+            backend: Backend::from_id(fragment[BACKEND_ID_OFFSET]),
+            backend_version: read_u32(fragment, BACKEND_VERSION_OFFSET),
+            libec_version: read_u32(fragment, LIBEC_VERSION_OFFSET),
+        })
+    }
+
+    /// Returns the index of the fragment.
+    ///
+    /// Indices in `0..data_fragments` are data fragments and the rest are parity fragments.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the size of the payload (i.e., the fragment excluding the header) in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the size of the backend specific metadata in bytes.
+    pub fn backend_metadata_size(&self) -> usize {
+        self.backend_metadata_size
+    }
+
+    /// Returns the size of the original (i.e., encoded) data in bytes.
+    pub fn original_data_size(&self) -> u64 {
+        self.original_data_size
+    }
+
+    /// Returns the type of the checksum of the payload.
+    pub fn checksum_type(&self) -> Checksum {
+        self.checksum_type
+    }
+
+    /// Returns the checksum value of the payload.
+    ///
+    /// The length of the returned slice depends on the checksum type
+    /// (`Checksum::None`: 0, `Checksum::Crc32`: 1, `Checksum::Md5`: 4).
+    pub fn checksum(&self) -> &[u32] {
+        match self.checksum_type {
+            Checksum::None => &[],
+            Checksum::Crc32 => &self.checksum[..1],
+            Checksum::Md5 => &self.checksum[..4],
+        }
+    }
+
+    /// Returns `true` if the checksum stored in the header does not match the payload.
+    ///
+    /// Note that [openstack/liberasurecode] only verifies `Checksum::Crc32` checksums,
+    /// so this always returns `false` for the other checksum types.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn checksum_mismatch(&self) -> bool {
+        self.checksum_mismatch
+    }
+
+    /// Returns the backend used to encode the fragment.
+    ///
+    /// `None` is returned if the backend is not supported by this crate.
+    pub fn backend(&self) -> Option<Backend> {
+        self.backend
+    }
+
+    /// Returns the version of the backend used to encode the fragment.
+    ///
+    /// The version is encoded as `(major << 16) | (minor << 8) | revision`.
+    pub fn backend_version(&self) -> u32 {
+        self.backend_version
+    }
+
+    /// Returns the version of [openstack/liberasurecode] used to encode the fragment.
+    ///
+    /// The version is encoded as `(major << 16) | (minor << 8) | revision`.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    pub fn libec_version(&self) -> u32 {
+        self.libec_version
+    }
+}
+
+/// Checks that the header and the payload of the given fragment are within its bounds.
+///
+/// [openstack/liberasurecode] trusts the header blindly, so this must be checked before passing
+/// the fragment to the C functions.
+///
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+fn check_header(fragment: &[u8]) -> std::result::Result<(), FragmentError> {
+    if fragment.len() < FRAGMENT_HEADER_SIZE {
+        return Err(FragmentError::InconsistentSize);
+    }
+    if read_u32(fragment, MAGIC_OFFSET) != FRAGMENT_HEADER_MAGIC {
+        return Err(FragmentError::BadMagic);
+    }
+    let size = u64::from(read_u32(fragment, SIZE_OFFSET));
+    let backend_metadata_size = u64::from(read_u32(fragment, BACKEND_METADATA_SIZE_OFFSET));
+    if ((fragment.len() - FRAGMENT_HEADER_SIZE) as u64) < size + backend_metadata_size {
+        return Err(FragmentError::InconsistentSize);
+    }
+    Ok(())
+}
+
+/// Returns `false` if `libec_version` is zero or `metadata_chksum` does not match
+/// (as `is_invalid_fragment_header` of [openstack/liberasurecode]).
+///
+/// The fragment must be longer than the header.
+///
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+pub(crate) fn is_valid_header_checksum(fragment: &[u8]) -> bool {
+    let libec_version = read_u32(fragment, LIBEC_VERSION_OFFSET);
+    if libec_version == 0 {
+        return false;
+    }
+    if libec_version < METADATA_CHECKSUM_MIN_LIBEC_VERSION {
+        // Older versions do not store the checksum.
+        return true;
+    }
+    let checksum = read_u32(fragment, METADATA_CHECKSUM_OFFSET);
+    crc32(&fragment[..FRAGMENT_METADATA_SIZE]) == checksum
+}
+
+/// Checks that the given fragments can be safely passed to the C functions
+/// which take a single length for all the fragments (e.g., `liberasurecode_decode`).
+///
+/// # Errors
+///
+/// `Error::InvalidFragment` is returned with the position of the first malformed fragment and
+/// one of the following reasons:
+/// - `FragmentError::InconsistentSize` if its length differs from that of the first fragment,
+///   or it is too short for its header and payload,
+/// - `FragmentError::BadMagic` if its header has a bad magic number,
+/// - `FragmentError::InvalidIndex` if its index is not less than `total_fragments`,
+/// - `FragmentError::InvalidOriginalDataSize` if its original data size exceeds
+///   the total size of the payloads of `data_fragments` fragments.
+pub(crate) fn check_fragments(
+    fragments: &[&[u8]],
+    data_fragments: usize,
+    total_fragments: usize,
+) -> Result<()> {
+    let fragment_len = fragments.first().map_or(0, |fragment| fragment.len());
+    for (i, fragment) in fragments.iter().enumerate() {
+        let invalid = |reason| Err(Error::InvalidFragment(i, reason));
+        if fragment.len() != fragment_len {
+            return invalid(FragmentError::InconsistentSize);
+        }
+        if let Err(reason) = check_header(fragment) {
+            return invalid(reason);
+        }
+        if read_u32(fragment, INDEX_OFFSET) as usize >= total_fragments {
+            return invalid(FragmentError::InvalidIndex);
+        }
+        let size = u64::from(read_u32(fragment, SIZE_OFFSET));
+        if read_u64(fragment, ORIG_DATA_SIZE_OFFSET) > size * data_fragments as u64 {
+            return invalid(FragmentError::InvalidOriginalDataSize);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[offset..][..4]);
+    u32::from_ne_bytes(buf)
+}
+
+pub(crate) fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[offset..][..8]);
+    u64::from_ne_bytes(buf)
+}
+
+pub(crate) fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..][..4].copy_from_slice(&value.to_ne_bytes());
+}
+
+pub(crate) fn write_u64(bytes: &mut [u8], offset: usize, value: u64) {
+    bytes[offset..][..8].copy_from_slice(&value.to_ne_bytes());
+}
@@ -0,0 +1,329 @@
+use std::cmp;
+
+use crate::crc32::crc32;
+use crate::gf;
+use crate::metadata::{self, FragmentMetadata};
+use crate::{Checksum, Error, Result};
+
+/// The backend ID recorded in the headers of the fragments encoded by `Backend::PureRustRs`.
+///
+/// This is out of the range of the IDs assigned by [openstack/liberasurecode].
+///
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+pub(crate) const BACKEND_ID: u8 = 0x80;
+
+/// The version of the encoding scheme, recorded in the headers as the backend version.
+pub(crate) const BACKEND_VERSION: u32 = 0x01_00_00;
+
+/// The payload size of each fragment is a multiple of this.
+const ALIGNMENT: usize = 16;
+
+/// The number of bytes of each fragment processed at a time by `mul_add_matrix`.
+///
+/// This keeps the chunks of all the output fragments in the L1/L2 cache while the inputs stream in.
+const CHUNK_SIZE: usize = 4096;
+
+/// Systematic Reed-Solomon erasure coding over GF(2^8) based on a Cauchy matrix.
+///
+/// The first `data_fragments` fragments hold the (zero padded) data as is,
+/// and the parity fragments are the products of the data and the Cauchy matrix.
+/// Since every square sub-matrix of a Cauchy matrix is invertible,
+/// the data can be decoded from any `data_fragments` fragments.
+///
+/// The fragments start with the same header as those of [openstack/liberasurecode].
+///
+/// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+#[derive(Debug)]
+pub(crate) struct ReedSolomon {
+    data_fragments: usize,
+    parity_fragments: usize,
+    checksum: Checksum,
+    checksum_type: u8,
+
+    /// `parity_matrix[i][j] = 1 / (i + (parity_fragments + j))` (`+` is XOR in GF(2^8)).
+    parity_matrix: Vec<Vec<u8>>,
+}
+impl ReedSolomon {
+    /// Makes a new `ReedSolomon` instance.
+    ///
+    /// `data_fragments + parity_fragments` must be less than or equal to 256.
+    pub fn new(data_fragments: usize, parity_fragments: usize, checksum: Checksum) -> Self {
+        assert!(data_fragments + parity_fragments <= 256);
+        let parity_matrix = (0..parity_fragments)
+            .map(|i| {
+                (0..data_fragments)
+                    .map(|j| gf::inv((i ^ (parity_fragments + j)) as u8))
+                    .collect()
+            })
+            .collect();
+        ReedSolomon {
+            data_fragments,
+            parity_fragments,
+            checksum,
+            checksum_type: checksum.id(),
+            parity_matrix,
+        }
+    }
+
+    fn fragments(&self) -> usize {
+        self.data_fragments + self.parity_fragments
+    }
+
+    /// Returns the size of the given length of data after padded for encoding.
+    pub fn aligned_data_size(&self, data_len: usize) -> usize {
+        let unit = self.minimum_encode_size();
+        cmp::max(data_len, 1).div_ceil(unit) * unit
+    }
+
+    pub fn minimum_encode_size(&self) -> usize {
+        self.data_fragments * ALIGNMENT
+    }
+
+    /// Returns the size of each fragment excluding its header.
+    pub fn fragment_payload_size(&self, data_len: usize) -> usize {
+        self.aligned_data_size(data_len) / self.data_fragments
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Vec<Vec<u8>> {
+        let fragment_len = FragmentMetadata::HEADER_SIZE + self.fragment_payload_size(data.len());
+        let mut fragments = vec![vec![0; fragment_len]; self.fragments()];
+        let mut buffers = fragments
+            .iter_mut()
+            .map(|fragment| &mut fragment[..])
+            .collect::<Vec<_>>();
+        self.encode_into(data, &mut buffers);
+        fragments
+    }
+
+    /// Encodes `data` directly into `fragments`, and returns the size of each fragment.
+    ///
+    /// `fragments` must consist of `data_fragments + parity_fragments` buffers, and each of them
+    /// must be at least `HEADER_SIZE + fragment_payload_size(data.len())` bytes long.
+    /// The bytes after the fragment in each buffer are left untouched.
+    pub fn encode_into(&self, data: &[u8], fragments: &mut [&mut [u8]]) -> usize {
+        let payload_size = self.fragment_payload_size(data.len());
+        let fragment_len = FragmentMetadata::HEADER_SIZE + payload_size;
+        for fragment in fragments.iter_mut() {
+            // The buffers may hold arbitrary bytes, and the parity is accumulated by XOR.
+            fragment[..fragment_len].fill(0);
+        }
+        for (fragment, chunk) in fragments.iter_mut().zip(data.chunks(payload_size)) {
+            fragment[FragmentMetadata::HEADER_SIZE..][..chunk.len()].copy_from_slice(chunk);
+        }
+
+        let (data_fragments, parity_fragments) = fragments.split_at_mut(self.data_fragments);
+        let inputs = data_fragments
+            .iter()
+            .map(|fragment| &fragment[FragmentMetadata::HEADER_SIZE..fragment_len])
+            .collect::<Vec<_>>();
+        let mut outputs = parity_fragments
+            .iter_mut()
+            .map(|fragment| &mut fragment[FragmentMetadata::HEADER_SIZE..fragment_len])
+            .collect::<Vec<_>>();
+        mul_add_matrix(&self.parity_matrix, &inputs, &mut outputs);
+        for (index, fragment) in fragments.iter_mut().enumerate() {
+            self.write_header(&mut fragment[..fragment_len], index, data.len() as u64);
+        }
+        fragment_len
+    }
+
+    /// Decodes the original data from the given fragments.
+    ///
+    /// The fragments must have been checked by `metadata::check_fragments`.
+    pub fn decode(&self, fragments: &[&[u8]]) -> Result<Vec<u8>> {
+        let data_len = self.original_data_size(fragments)?;
+        let blocks = self.decode_blocks(fragments)?;
+        let mut data = Vec::with_capacity(data_len);
+        for block in &blocks {
+            let len = cmp::min(block.len(), data_len - data.len());
+            data.extend_from_slice(&block[..len]);
+        }
+        Ok(data)
+    }
+
+    /// Reconstructs the fragment specified by `index` from the given fragments.
+    ///
+    /// The fragments must have been checked by `metadata::check_fragments`.
+    pub fn reconstruct(&self, fragments: &[&[u8]], index: usize) -> Result<Vec<u8>> {
+        let mut reconstructed = self.reconstruct_many(fragments, &[index])?;
+        Ok(reconstructed.remove(0))
+    }
+
+    /// Reconstructs the fragments specified by `indices` from the given fragments
+    /// (in the order of `indices`).
+    ///
+    /// The fragments are decoded only once for all the indices.
+    /// The fragments must have been checked by `metadata::check_fragments`.
+    pub fn reconstruct_many(&self, fragments: &[&[u8]], indices: &[usize]) -> Result<Vec<Vec<u8>>> {
+        let data_len = self.original_data_size(fragments)?;
+        let blocks = self.decode_blocks(fragments)?;
+        let inputs = blocks.iter().map(|block| &block[..]).collect::<Vec<_>>();
+        let mut reconstructed = Vec::with_capacity(indices.len());
+        for &index in indices {
+            let mut fragment = vec![0; FragmentMetadata::HEADER_SIZE + blocks[0].len()];
+            let payload = &mut fragment[FragmentMetadata::HEADER_SIZE..];
+            if index < self.data_fragments {
+                payload.copy_from_slice(&blocks[index]);
+            } else {
+                let row = &self.parity_matrix[index - self.data_fragments..][..1];
+                mul_add_matrix(row, &inputs, &mut [payload]);
+            }
+            self.write_header(&mut fragment, index, data_len as u64);
+            reconstructed.push(fragment);
+        }
+        Ok(reconstructed)
+    }
+
+    /// Returns the indices of the fragments needed to reconstruct `to_reconstruct`
+    /// without using `to_exclude`.
+    ///
+    /// Data fragments are preferred since they need no decoding.
+    pub fn fragments_needed(
+        &self,
+        to_reconstruct: &[usize],
+        to_exclude: &[usize],
+    ) -> Result<Vec<usize>> {
+        let needed = (0..self.fragments())
+            .filter(|i| !to_reconstruct.contains(i) && !to_exclude.contains(i))
+            .take(self.data_fragments)
+            .collect::<Vec<_>>();
+        if needed.len() < self.data_fragments {
+            return Err(Error::InsufficientFragments);
+        }
+        Ok(needed)
+    }
+
+    /// Returns `true` if the given fragment was encoded by a compatible version of this backend.
+    pub fn is_compatible_fragment(&self, metadata: &FragmentMetadata) -> bool {
+        metadata.backend_version() == BACKEND_VERSION
+    }
+
+    fn original_data_size(&self, fragments: &[&[u8]]) -> Result<usize> {
+        let data_len = metadata::read_u64(fragments[0], metadata::ORIG_DATA_SIZE_OFFSET);
+        if fragments.iter().any(|fragment| {
+            metadata::read_u64(fragment, metadata::ORIG_DATA_SIZE_OFFSET) != data_len
+        }) {
+            return Err(Error::BadHeader);
+        }
+        Ok(data_len as usize)
+    }
+
+    /// Decodes the payloads of the data fragments (including the padding).
+    fn decode_blocks<'a>(&self, fragments: &[&'a [u8]]) -> Result<Vec<Vec<u8>>> {
+        let payload_size = metadata::read_u32(fragments[0], metadata::SIZE_OFFSET) as usize;
+        let mut payloads: Vec<Option<&'a [u8]>> = vec![None; self.fragments()];
+        for fragment in fragments {
+            if metadata::read_u32(fragment, metadata::SIZE_OFFSET) as usize != payload_size {
+                return Err(Error::BadHeader);
+            }
+            let index = metadata::read_u32(fragment, metadata::INDEX_OFFSET) as usize;
+            if payloads[index].is_none() {
+                payloads[index] = Some(&fragment[FragmentMetadata::HEADER_SIZE..][..payload_size]);
+            }
+        }
+
+        // Uses the first `data_fragments` available fragments (i.e., data fragments are preferred).
+        let rows = (0..self.fragments())
+            .filter(|&i| payloads[i].is_some())
+            .take(self.data_fragments)
+            .collect::<Vec<_>>();
+        if rows.len() < self.data_fragments {
+            return Err(Error::InsufficientFragments);
+        }
+
+        let mut blocks = vec![Vec::new(); self.data_fragments];
+        let missing = (0..self.data_fragments)
+            .filter(|&i| payloads[i].is_none())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let mut matrix = rows
+                .iter()
+                .map(|&row| self.encoding_row(row))
+                .collect::<Vec<_>>();
+            let invertible = gf::invert_matrix(&mut matrix);
+            assert!(invertible, "Cauchy based matrices are always invertible");
+
+            let decoding_matrix = missing
+                .iter()
+                .map(|&i| matrix[i].clone())
+                .collect::<Vec<_>>();
+            let inputs = rows
+                .iter()
+                .map(|&row| payloads[row].unwrap())
+                .collect::<Vec<_>>();
+            let mut decoded = vec![vec![0; payload_size]; missing.len()];
+            let mut outputs = decoded
+                .iter_mut()
+                .map(|block| &mut block[..])
+                .collect::<Vec<_>>();
+            mul_add_matrix(&decoding_matrix, &inputs, &mut outputs);
+            for (&i, block) in missing.iter().zip(decoded) {
+                blocks[i] = block;
+            }
+        }
+        for (block, payload) in blocks.iter_mut().zip(payloads.iter()) {
+            if let Some(payload) = payload {
+                *block = payload.to_vec();
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Returns the row of the generator matrix for the fragment specified by `index`.
+    fn encoding_row(&self, index: usize) -> Vec<u8> {
+        if index < self.data_fragments {
+            let mut row = vec![0; self.data_fragments];
+            row[index] = 1;
+            row
+        } else {
+            self.parity_matrix[index - self.data_fragments].clone()
+        }
+    }
+
+    /// Writes the header of a fragment in the same layout as `fragment_header_t`
+    /// of [openstack/liberasurecode].
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    fn write_header(&self, fragment: &mut [u8], index: usize, data_len: u64) {
+        let (header, payload) = fragment.split_at_mut(FragmentMetadata::HEADER_SIZE);
+        metadata::write_u32(header, metadata::INDEX_OFFSET, index as u32);
+        metadata::write_u32(header, metadata::SIZE_OFFSET, payload.len() as u32);
+        metadata::write_u32(header, metadata::BACKEND_METADATA_SIZE_OFFSET, 0);
+        metadata::write_u64(header, metadata::ORIG_DATA_SIZE_OFFSET, data_len);
+        header[metadata::CHECKSUM_TYPE_OFFSET] = self.checksum_type;
+        if self.checksum == Checksum::Crc32 {
+            metadata::write_u32(header, metadata::CHECKSUM_OFFSET, crc32(payload));
+        }
+        header[metadata::BACKEND_ID_OFFSET] = BACKEND_ID;
+        metadata::write_u32(header, metadata::BACKEND_VERSION_OFFSET, BACKEND_VERSION);
+        metadata::write_u32(
+            header,
+            metadata::MAGIC_OFFSET,
+            metadata::FRAGMENT_HEADER_MAGIC,
+        );
+        metadata::write_u32(
+            header,
+            metadata::LIBEC_VERSION_OFFSET,
+            metadata::LIBERASURECODE_VERSION,
+        );
+        let checksum = crc32(&header[..metadata::FRAGMENT_METADATA_SIZE]);
+        metadata::write_u32(header, metadata::METADATA_CHECKSUM_OFFSET, checksum);
+    }
+}
+
+/// Computes `outputs[i] ^= sum_j(matrix[i][j] * inputs[j])`.
+///
+/// The fragments are processed in chunks of `CHUNK_SIZE` bytes,
+/// so that each chunk of the outputs is updated while it is hot in the cache
+/// instead of sweeping whole fragments `inputs.len()` times.
+fn mul_add_matrix(matrix: &[Vec<u8>], inputs: &[&[u8]], outputs: &mut [&mut [u8]]) {
+    let len = outputs.first().map_or(0, |output| output.len());
+    for offset in (0..len).step_by(CHUNK_SIZE) {
+        let end = cmp::min(offset + CHUNK_SIZE, len);
+        for (row, output) in matrix.iter().zip(outputs.iter_mut()) {
+            for (&coefficient, input) in row.iter().zip(inputs) {
+                gf::mul_add_slice(coefficient, &input[offset..end], &mut output[offset..end]);
+            }
+        }
+    }
+}
@@ -1,16 +1,28 @@
 use std::error;
 use std::fmt;
 
-use crate::c_api;
+use crate::verify::FragmentError;
 
 /// This crate specific [`Result`] type.
 ///
 /// [`Result`]: https://doc.rust-lang.org/std/result/enum.Result.html
 pub type Result<T> = std::result::Result<T, Error>;
 
+// The error codes defined in `erasurecode.h` of liberasurecode.
+const EBACKENDNOTSUPP: u32 = 200;
+const EECMETHODNOTIMPL: u32 = 201;
+const EBACKENDINITERR: u32 = 202;
+const EBACKENDINUSE: u32 = 203;
+const EBACKENDNOTAVAIL: u32 = 204;
+const EBADCHKSUM: u32 = 205;
+const EINVALIDPARAMS: u32 = 206;
+const EBADHEADER: u32 = 207;
+const EINSUFFFRAGS: u32 = 208;
+
 /// Possible errors.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub enum Error {
     BackendNotSupported,
     EcMethodNotImplemented,
@@ -21,6 +33,11 @@ pub enum Error {
     InvalidParams,
     BadHeader,
     InsufficientFragments,
+
+    /// The fragment at the given position (in the given slice) is malformed for the reason.
+    ///
+    /// This is detected by this crate before passing the fragments to the backend.
+    InvalidFragment(usize, FragmentError),
     Other(u32),
 }
 impl Error {
@@ -29,15 +46,15 @@ impl Error {
     /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
     pub fn from_error_code(code: u32) -> Self {
         match code {
-            c_api::EBACKENDNOTSUPP => Error::BackendNotSupported,
-            c_api::EECMETHODNOTIMPL => Error::EcMethodNotImplemented,
-            c_api::EBACKENDINITERR => Error::BackendInitError,
-            c_api::EBACKENDINUSE => Error::BackendInUse,
-            c_api::EBACKENDNOTAVAIL => Error::BackendNotAvailable,
-            c_api::EBADCHKSUM => Error::BadChecksum,
-            c_api::EINVALIDPARAMS => Error::InvalidParams,
-            c_api::EBADHEADER => Error::BadHeader,
-            c_api::EINSUFFFRAGS => Error::InsufficientFragments,
+            EBACKENDNOTSUPP => Error::BackendNotSupported,
+            EECMETHODNOTIMPL => Error::EcMethodNotImplemented,
+            EBACKENDINITERR => Error::BackendInitError,
+            EBACKENDINUSE => Error::BackendInUse,
+            EBACKENDNOTAVAIL => Error::BackendNotAvailable,
+            EBADCHKSUM => Error::BadChecksum,
+            EINVALIDPARAMS => Error::InvalidParams,
+            EBADHEADER => Error::BadHeader,
+            EINSUFFFRAGS => Error::InsufficientFragments,
             _ => Error::Other(code),
         }
     }
@@ -45,15 +62,16 @@ impl Error {
     /// Returns the codepoint of the error.
     pub fn as_error_code(self) -> u32 {
         match self {
-            Error::BackendNotSupported => c_api::EBACKENDNOTSUPP,
-            Error::EcMethodNotImplemented => c_api::EECMETHODNOTIMPL,
-            Error::BackendInitError => c_api::EBACKENDINITERR,
-            Error::BackendInUse => c_api::EBACKENDINUSE,
-            Error::BackendNotAvailable => c_api::EBACKENDNOTAVAIL,
-            Error::BadChecksum => c_api::EBADCHKSUM,
-            Error::InvalidParams => c_api::EINVALIDPARAMS,
-            Error::BadHeader => c_api::EBADHEADER,
-            Error::InsufficientFragments => c_api::EINSUFFFRAGS,
+            Error::BackendNotSupported => EBACKENDNOTSUPP,
+            Error::EcMethodNotImplemented => EECMETHODNOTIMPL,
+            Error::BackendInitError => EBACKENDINITERR,
+            Error::BackendInUse => EBACKENDINUSE,
+            Error::BackendNotAvailable => EBACKENDNOTAVAIL,
+            Error::BadChecksum => EBADCHKSUM,
+            Error::InvalidParams => EINVALIDPARAMS,
+            Error::BadHeader => EBADHEADER,
+            Error::InsufficientFragments => EINSUFFFRAGS,
+            Error::InvalidFragment(..) => EBADHEADER,
             Error::Other(code) => code,
         }
     }
@@ -72,6 +90,9 @@ impl fmt::Display for Error {
             Error::InvalidParams => write!(f, "Invalid parameters"),
             Error::BadHeader => write!(f, "Bad header"),
             Error::InsufficientFragments => write!(f, "Insufficient fragments"),
+            Error::InvalidFragment(position, reason) => {
+                write!(f, "Invalid fragment (position={}): {}", position, reason)
+            }
             Error::Other(code) => write!(f, "Unknown error (code={})", code),
         }
     }
@@ -88,6 +109,7 @@ impl error::Error for Error {
             Error::InvalidParams => "Invalid parameters",
             Error::BadHeader => "Bad header",
             Error::InsufficientFragments => "Insufficient fragments",
+            Error::InvalidFragment(..) => "Invalid fragment",
             Error::Other(_) => "Unknown error",
         }
     }
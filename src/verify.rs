@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(feature = "native")]
+use crate::c_api;
+use crate::metadata::{self, FragmentMetadata};
+use crate::{Engine, ErasureCoder, Error, Result};
+
+/// Reasons why a fragment is considered invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FragmentError {
+    /// The header does not start with the magic number of [openstack/liberasurecode].
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    BadMagic,
+
+    /// The checksum of the header does not match its content.
+    BadHeaderChecksum,
+
+    /// The fragment was encoded by a backend different from that of the coder.
+    BackendMismatch,
+
+    /// The fragment was encoded by an incompatible version of the backend
+    /// or [openstack/liberasurecode].
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    IncompatibleVersion,
+
+    /// The index recorded in the header is out of the range of the coder.
+    InvalidIndex,
+
+    /// The length of the fragment is inconsistent with its header or the other fragments.
+    InconsistentSize,
+
+    /// The checksum of the payload does not match.
+    BadChecksum,
+
+    /// The original data size recorded in the header exceeds the total size of
+    /// the payloads of the data fragments.
+    InvalidOriginalDataSize,
+
+    /// The header is rejected by [openstack/liberasurecode] as malformed
+    /// (e.g., with `-EBADHEADER` or `-EINVALIDPARAMS`) for a reason not covered by the other variants.
+    ///
+    /// [openstack/liberasurecode]: https://github.com/openstack/liberasurecode
+    Malformed,
+}
+impl fmt::Display for FragmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FragmentError::BadMagic => write!(f, "Bad magic number"),
+            FragmentError::BadHeaderChecksum => write!(f, "Bad header checksum"),
+            FragmentError::BackendMismatch => write!(f, "Mismatched backend"),
+            FragmentError::IncompatibleVersion => write!(f, "Incompatible version"),
+            FragmentError::InvalidIndex => write!(f, "Invalid fragment index"),
+            FragmentError::InconsistentSize => write!(f, "Inconsistent fragment size"),
+            FragmentError::BadChecksum => write!(f, "Bad checksum value"),
+            FragmentError::InvalidOriginalDataSize => write!(f, "Invalid original data size"),
+            FragmentError::Malformed => write!(f, "Malformed fragment"),
+        }
+    }
+}
+
+/// The result of [`ErasureCoder::verify_stripe`].
+///
+/// [`ErasureCoder::verify_stripe`]: ./struct.ErasureCoder.html#method.verify_stripe
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StripeReport {
+    invalid_fragments: Vec<(usize, FragmentError)>,
+}
+impl StripeReport {
+    /// Returns `true` if all the fragments in the stripe are valid, otherwise `false`.
+    pub fn is_valid(&self) -> bool {
+        self.invalid_fragments.is_empty()
+    }
+
+    /// Returns the invalid fragments in the stripe.
+    ///
+    /// Each item is a pair of the position of the fragment in the given slice and
+    /// the reason why it is invalid.
+    pub fn invalid_fragments(&self) -> &[(usize, FragmentError)] {
+        &self.invalid_fragments
+    }
+}
+
+/// Diagnoses the given fragment and returns the first problem found.
+pub(crate) fn diagnose_fragment(coder: &ErasureCoder, fragment: &[u8]) -> Option<FragmentError> {
+    if fragment.len() < metadata::FRAGMENT_HEADER_SIZE {
+        return Some(FragmentError::InconsistentSize);
+    }
+    if metadata::read_u32(fragment, metadata::MAGIC_OFFSET) != metadata::FRAGMENT_HEADER_MAGIC {
+        return Some(FragmentError::BadMagic);
+    }
+    if metadata::read_u32(fragment, metadata::LIBEC_VERSION_OFFSET) == 0 {
+        // liberasurecode never writes version zero (and rejects such headers).
+        return Some(FragmentError::IncompatibleVersion);
+    }
+    if !metadata::is_valid_header_checksum(fragment) {
+        return Some(FragmentError::BadHeaderChecksum);
+    }
+
+    let metadata = match FragmentMetadata::parse(fragment) {
+        Err(_) => return Some(FragmentError::InconsistentSize),
+        Ok(metadata) => metadata,
+    };
+    if metadata.backend() != Some(coder.backend) {
+        return Some(FragmentError::BackendMismatch);
+    }
+    if metadata::FRAGMENT_HEADER_SIZE + metadata.size() + metadata.backend_metadata_size()
+        != fragment.len()
+    {
+        return Some(FragmentError::InconsistentSize);
+    }
+    if metadata.index() >= coder.fragments().get() {
+        return Some(FragmentError::InvalidIndex);
+    }
+    if metadata.checksum_mismatch() {
+        return Some(FragmentError::BadChecksum);
+    }
+    let is_compatible = match coder.engine {
+        #[cfg(feature = "native")]
+        Engine::Native(desc) => c_api::is_valid_fragment(desc, fragment),
+        Engine::PureRust(ref rs) => rs.is_compatible_fragment(&metadata),
+    };
+    if !is_compatible {
+        return Some(FragmentError::IncompatibleVersion);
+    }
+    None
+}
+
+/// Verifies the consistency of the given fragments.
+pub(crate) fn verify_stripe(coder: &ErasureCoder, fragments: &[&[u8]]) -> Result<StripeReport> {
+    if fragments.is_empty() {
+        return Err(Error::InvalidParams);
+    }
+
+    let mut invalid_fragments = Vec::new();
+    let mut valid_fragments = Vec::new();
+    for (i, fragment) in fragments.iter().enumerate() {
+        match diagnose_fragment(coder, fragment) {
+            Some(e) => invalid_fragments.push((i, e)),
+            None => {
+                let metadata = FragmentMetadata::parse(fragment)?;
+                valid_fragments.push((i, metadata, fragment.len()));
+            }
+        }
+    }
+
+    // Fragments that disagree with the majority about their sizes are considered inconsistent.
+    let mut sizes = HashMap::new();
+    for (_, metadata, len) in &valid_fragments {
+        *sizes
+            .entry((*len, metadata.original_data_size()))
+            .or_insert(0) += 1;
+    }
+    let majority = sizes
+        .into_iter()
+        .max_by_key(|&(size, count)| (count, size))
+        .map(|(size, _)| size);
+    let mut consistent_fragments = Vec::new();
+    for (i, metadata, len) in valid_fragments {
+        if Some((len, metadata.original_data_size())) == majority {
+            consistent_fragments.push((i, fragments[i]));
+        } else {
+            invalid_fragments.push((i, FragmentError::InconsistentSize));
+        }
+    }
+
+    #[cfg(feature = "native")]
+    {
+        if let Engine::Native(desc) = coder.engine {
+            // liberasurecode checks each fragment independently (and reports only the first error),
+            // so the stripe is re-checked fragment by fragment to find the culprits.
+            let stripe = consistent_fragments
+                .iter()
+                .map(|&(_, f)| f)
+                .collect::<Vec<_>>();
+            if !stripe.is_empty() && c_api::verify_stripe_metadata(desc, &stripe).is_err() {
+                for &(i, fragment) in &consistent_fragments {
+                    if let Err(code) = c_api::verify_stripe_metadata(desc, &[fragment]) {
+                        // The backends and their versions have been checked by `diagnose_fragment`.
+                        let e = match Error::from_error_code(code) {
+                            Error::BadChecksum => FragmentError::BadChecksum,
+                            Error::BadHeader | Error::InvalidParams => FragmentError::Malformed,
+                            e => return Err(e),
+                        };
+                        invalid_fragments.push((i, e));
+                    }
+                }
+            }
+        }
+    }
+    invalid_fragments.sort_by_key(|&(i, _)| i);
+    Ok(StripeReport { invalid_fragments })
+}
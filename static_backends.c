@@ -0,0 +1,116 @@
+/*
+ * Resolves the backends linked into the static liberasurecode library.
+ *
+ * `static_symbols.h` is generated by `install_deps.sh` from the symbols
+ * exported by the static backend libraries. The backends are enabled by
+ * `STATIC_BACKEND_<LIBRARY NAME>` macros according to the cargo features.
+ */
+#include <dlfcn.h>
+#include <pthread.h>
+#include <stddef.h>
+#include <string.h>
+
+struct static_symbol {
+    const char *name;
+    void *addr;
+};
+
+#include "static_symbols.h"
+
+static const char *static_sonames[] = {
+#ifdef STATIC_BACKEND_JERASURE
+    "libJerasure",
+#endif
+#ifdef STATIC_BACKEND_XORCODE
+    "libXorcode",
+#endif
+#ifdef STATIC_BACKEND_ISAL
+    "libisal",
+#endif
+#ifdef STATIC_BACKEND_NULLCODE
+    "libnullcode",
+#endif
+#ifdef STATIC_BACKEND_ERASURECODE_RS_VAND
+    "liberasurecode_rs_vand",
+#endif
+    NULL,
+};
+
+static int static_handle;
+
+/*
+ * `liberasurecode_rs_vand` keeps its GF tables in global variables, but
+ * (re)allocates them whenever an instance is created and frees them whenever
+ * an instance is destroyed. That breaks the other live instances, so the
+ * tables are initialized only once and never released.
+ */
+static pthread_mutex_t rs_vand_mutex = PTHREAD_MUTEX_INITIALIZER;
+static int rs_vand_initialized;
+
+static void *lookup_static_symbol(const char *symbol)
+{
+    int i;
+
+    for (i = 0; static_symbols[i].name != NULL; i++) {
+        if (strcmp(symbol, static_symbols[i].name) == 0) {
+            return static_symbols[i].addr;
+        }
+    }
+    return NULL;
+}
+
+static void init_liberasurecode_rs_vand_once(int k, int m)
+{
+    void (*init)(int, int);
+
+    pthread_mutex_lock(&rs_vand_mutex);
+    if (!rs_vand_initialized) {
+        *(void **)&init = lookup_static_symbol("init_liberasurecode_rs_vand");
+        init(k, m);
+        rs_vand_initialized = 1;
+    }
+    pthread_mutex_unlock(&rs_vand_mutex);
+}
+
+static void deinit_liberasurecode_rs_vand_noop(void)
+{
+}
+
+void *liberasurecode_static_dlopen(const char *filename, int flags)
+{
+    int i;
+
+    if (filename != NULL) {
+        for (i = 0; static_sonames[i] != NULL; i++) {
+            if (strncmp(filename, static_sonames[i], strlen(static_sonames[i])) == 0) {
+                return &static_handle;
+            }
+        }
+    }
+    return dlopen(filename, flags);
+}
+
+void *liberasurecode_static_dlsym(void *handle, const char *symbol)
+{
+    void *addr = NULL;
+
+    if (handle != &static_handle) {
+        return dlsym(handle, symbol);
+    }
+    if (strcmp(symbol, "init_liberasurecode_rs_vand") == 0) {
+        *(void (**)(int, int))&addr = init_liberasurecode_rs_vand_once;
+    } else if (strcmp(symbol, "deinit_liberasurecode_rs_vand") == 0) {
+        *(void (**)(void))&addr = deinit_liberasurecode_rs_vand_noop;
+    } else {
+        addr = lookup_static_symbol(symbol);
+    }
+    return addr;
+}
+
+int liberasurecode_static_dlclose(void *handle)
+{
+    if (handle == &static_handle) {
+        return 0;
+    }
+    return dlclose(handle);
+}
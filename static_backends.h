@@ -0,0 +1,22 @@
+/*
+ * Forcibly included into the liberasurecode sources (see `install_deps.sh`).
+ *
+ * liberasurecode loads its backends via `dlopen(3)`, but this crate links
+ * everything statically. The macros below redirect the `dl*` calls to
+ * `static_backends.c`, which resolves the statically linked backends
+ * from a symbol table generated at build time.
+ */
+#ifndef LIBERASURECODE_STATIC_BACKENDS_H
+#define LIBERASURECODE_STATIC_BACKENDS_H
+
+#include <dlfcn.h>
+
+void *liberasurecode_static_dlopen(const char *filename, int flags);
+void *liberasurecode_static_dlsym(void *handle, const char *symbol);
+int liberasurecode_static_dlclose(void *handle);
+
+#define dlopen liberasurecode_static_dlopen
+#define dlsym liberasurecode_static_dlsym
+#define dlclose liberasurecode_static_dlclose
+
+#endif
@@ -0,0 +1,152 @@
+#! /usr/bin/env python3
+#
+# Generates the golden fragment sets in `testdata/vectors/` by the reference liberasurecode.
+#
+# liberasurecode is called directly (via ctypes) rather than via PyECLib,
+# since PyECLib cannot specify the word size `w` nor the MD5 checksum type.
+# The parameters passed to `liberasurecode_instance_create` are pinned to those of this crate
+# (see `Backend::word_size`, `Backend::hamming_distance` and `Checksum::id` in `src/lib.rs`).
+#
+# The loaded liberasurecode must be the reference 1.5.0 (without `liberasurecode.patch`),
+# and the backend libraries must be the same revisions of gf-complete, jerasure and isa-l
+# as `install_deps.sh`. The version recorded in the fragment headers is checked,
+# and the script fails (instead of skipping the set) if any backend is not available.
+#
+# Each set is a directory named `<backend>-<checksum>-<k>-<m>-<size>` containing:
+# - `params`: `key=value` lines of the parameters
+# - `data.bin`: the original data
+# - `fragment-<index>.bin`: the encoded fragments
+#
+# Usage: ./testdata/generate_vectors.py [path to liberasurecode.so] (at the root of this crate)
+
+import ctypes
+import os
+import shutil
+import struct
+import sys
+
+LIBERASURECODE_VERSION = 0x010500
+LIBEC_VERSION_OFFSET = 63
+FRAGMENT_HEADER_SIZE = 80
+
+# `ec_backend_id_t`
+BACKEND_IDS = {
+    'jerasure_rs_vand': 1,
+    'jerasure_rs_cauchy': 2,
+    'flat_xor_hd': 3,
+    'isa_l_rs_vand': 4,
+    'liberasurecode_rs_vand': 6,
+    'isa_l_rs_cauchy': 7,
+}
+
+# (name in this crate, data fragments, parity fragments, w, hd)
+BACKENDS = [
+    ('jerasure_rs_vand', 4, 2, 32, 2),
+    ('jerasure_rs_vand', 10, 4, 32, 4),
+    ('jerasure_rs_cauchy', 4, 2, 32, 2),
+    ('jerasure_rs_cauchy', 10, 4, 32, 4),
+    ('flat_xor_hd', 3, 3, 32, 3),
+    ('flat_xor_hd', 5, 5, 32, 4),
+    ('isa_l_rs_vand', 4, 2, 8, 2),
+    ('isa_l_rs_cauchy', 4, 2, 8, 2),
+    ('liberasurecode_rs_vand', 4, 2, 16, 2),
+]
+
+# (name in this crate, `ec_checksum_type_t`)
+CHECKSUMS = [('none', 1), ('crc32', 2), ('md5', 3)]
+
+DATA_SIZES = [1, 4097]
+
+OUTPUT_DIR = os.path.join(os.path.dirname(os.path.abspath(__file__)), 'vectors')
+
+
+class EcArgs(ctypes.Structure):
+    # `struct ec_args` (the same layout as `c_api::EcArgs`)
+    _fields_ = [
+        ('k', ctypes.c_int),
+        ('m', ctypes.c_int),
+        ('w', ctypes.c_int),
+        ('hd', ctypes.c_int),
+        ('priv_args', ctypes.c_uint64 * 5),
+        ('ct', ctypes.c_int),
+    ]
+
+
+def load_library():
+    path = sys.argv[1] if len(sys.argv) > 1 else 'liberasurecode.so.1'
+    lib = ctypes.CDLL(path)
+    lib.liberasurecode_instance_create.argtypes = [ctypes.c_int, ctypes.POINTER(EcArgs)]
+    lib.liberasurecode_instance_destroy.argtypes = [ctypes.c_int]
+    lib.liberasurecode_encode.argtypes = [
+        ctypes.c_int,
+        ctypes.c_char_p,
+        ctypes.c_uint64,
+        ctypes.POINTER(ctypes.POINTER(ctypes.POINTER(ctypes.c_char))),
+        ctypes.POINTER(ctypes.POINTER(ctypes.POINTER(ctypes.c_char))),
+        ctypes.POINTER(ctypes.c_uint64),
+    ]
+    lib.liberasurecode_encode_cleanup.argtypes = [
+        ctypes.c_int,
+        ctypes.POINTER(ctypes.POINTER(ctypes.c_char)),
+        ctypes.POINTER(ctypes.POINTER(ctypes.c_char)),
+    ]
+    return lib
+
+
+def make_data(size):
+    return bytes((i * 31 + 7) % 251 for i in range(size))
+
+
+def encode(lib, desc, k, m, data):
+    encoded_data = ctypes.POINTER(ctypes.POINTER(ctypes.c_char))()
+    encoded_parity = ctypes.POINTER(ctypes.POINTER(ctypes.c_char))()
+    fragment_len = ctypes.c_uint64()
+    code = lib.liberasurecode_encode(desc, data, len(data), ctypes.byref(encoded_data),
+                                     ctypes.byref(encoded_parity), ctypes.byref(fragment_len))
+    if code != 0:
+        sys.exit('liberasurecode_encode failed: {}'.format(code))
+    fragments = [ctypes.string_at(encoded_data[i], fragment_len.value) for i in range(k)]
+    fragments += [ctypes.string_at(encoded_parity[i], fragment_len.value) for i in range(m)]
+    lib.liberasurecode_encode_cleanup(desc, encoded_data, encoded_parity)
+    return fragments
+
+
+def main():
+    lib = load_library()
+    shutil.rmtree(OUTPUT_DIR, ignore_errors=True)
+    os.makedirs(OUTPUT_DIR)
+    for backend, k, m, w, hd in BACKENDS:
+        for checksum, ct in CHECKSUMS:
+            args = EcArgs(k=k, m=m, w=w, hd=hd, ct=ct)
+            desc = lib.liberasurecode_instance_create(BACKEND_IDS[backend], ctypes.byref(args))
+            if desc <= 0:
+                sys.exit('cannot create {} (k={}, m={}, w={}, hd={}, ct={}): {}'.format(
+                    backend, k, m, w, hd, ct, desc))
+            for size in DATA_SIZES:
+                data = make_data(size)
+                fragments = encode(lib, desc, k, m, data)
+                for fragment in fragments:
+                    if len(fragment) < FRAGMENT_HEADER_SIZE:
+                        sys.exit('{}: too short fragment'.format(backend))
+                    (version,) = struct.unpack_from('=I', fragment, LIBEC_VERSION_OFFSET)
+                    if version != LIBERASURECODE_VERSION:
+                        sys.exit('liberasurecode {:#08x} is loaded, but {:#08x} is required'.format(
+                            version, LIBERASURECODE_VERSION))
+
+                name = '{}-{}-{}-{}-{}'.format(backend, checksum, k, m, size)
+                set_dir = os.path.join(OUTPUT_DIR, name)
+                os.makedirs(set_dir)
+                with open(os.path.join(set_dir, 'params'), 'w') as f:
+                    f.write('backend={}\nchecksum={}\ndata_fragments={}\nparity_fragments={}\n'.format(
+                        backend, checksum, k, m))
+                with open(os.path.join(set_dir, 'data.bin'), 'wb') as f:
+                    f.write(data)
+                for index, fragment in enumerate(fragments):
+                    with open(os.path.join(set_dir, 'fragment-{}.bin'.format(index)), 'wb') as f:
+                        f.write(fragment)
+                print(name)
+            lib.liberasurecode_instance_destroy(desc)
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,57 @@
+#! /usr/bin/env bash
+#
+# Imports the sources of gf-complete, jerasure, isa-l and liberasurecode into `vendor/`
+# for the `vendored` feature.
+#
+# This runs `install_deps.sh` in a temporary directory, so it requires the same tools
+# (and network access). The imported sources are already configured and patched,
+# so building them requires only a C compiler (see `build_vendored_library` in `build.rs`).
+#
+# Usage: ./vendor.sh (at the root of this crate)
+
+set -eux
+
+ROOT_DIR=$PWD
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "${WORK_DIR}"' EXIT
+
+for file in install_deps.sh liberasurecode.patch for_darwin_to_detect_compiler_flag.patch \
+            static_backends.h static_backends.c; do
+    cp $file ${WORK_DIR}/
+done
+(cd ${WORK_DIR} && WITH_JERASURE=true WITH_FLAT_XOR=true WITH_ISA_L=true ./install_deps.sh)
+
+rm -rf vendor
+mkdir vendor
+for project in gf-complete jerasure liberasurecode; do
+    mkdir vendor/${project}
+    cp -R ${WORK_DIR}/${project}/src ${WORK_DIR}/${project}/include vendor/${project}/
+    # The headers generated by `configure` may be located at the top directory.
+    find ${WORK_DIR}/${project} -maxdepth 1 -name 'config*.h' -exec cp {} vendor/${project}/include/ \;
+    cp ${WORK_DIR}/${project}/COPYING vendor/${project}/ 2>/dev/null || \
+        cp ${WORK_DIR}/${project}/LICENSE vendor/${project}/
+    (cd ${WORK_DIR}/${project} && git rev-parse HEAD) > vendor/${project}/REVISION
+done
+
+# ISA-L has no `src` directory, so its erasure coding sources are placed in `vendor/isa-l/src`.
+mkdir -p vendor/isa-l/src
+cp -R ${WORK_DIR}/isa-l/erasure_code ${WORK_DIR}/isa-l/include vendor/isa-l/
+mv vendor/isa-l/erasure_code vendor/isa-l/src/
+cp ${WORK_DIR}/isa-l/LICENSE vendor/isa-l/
+(cd ${WORK_DIR}/isa-l && git rev-parse HEAD) > vendor/isa-l/REVISION
+cp ${WORK_DIR}/static_symbols.h vendor/
+
+# `liberasurecode.patch` is needed only if Jerasure is linked (i.e., the `jerasure` feature),
+# so the patched files are moved to `vendor/liberasurecode-jerasure` and the original ones are restored.
+for file in $(sed -n 's|^+++ b/||p' liberasurecode.patch); do
+    mkdir -p vendor/liberasurecode-jerasure/$(dirname $file)
+    cp vendor/liberasurecode/$file vendor/liberasurecode-jerasure/$file
+done
+patch -R -p1 -d vendor/liberasurecode < liberasurecode.patch
+
+# Removes the build artifacts.
+find vendor \( -name '*.o' -o -name '*.lo' -o -name '*.la' -o -name '*.a' \
+               -o -name 'Makefile*' -o -name '.deps' -o -name '.libs' -o -name '.dirstamp' \) \
+     -prune -exec rm -rf {} +
+
+echo "Imported the sources into ${ROOT_DIR}/vendor"